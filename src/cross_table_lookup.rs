@@ -0,0 +1,352 @@
+//! Cross-table lookup (CTL) support.
+//!
+//! A single STARK only proves facts about its own trace. Once more than
+//! one table exists (`ProgramInstructionsStark`, `CpuStark`, and later
+//! `MemoryStark`), we additionally need to prove that rows one table
+//! claims to have *looked up* really do appear in the table that is
+//! supposed to be *looked into* — otherwise a prover could execute an
+//! instruction that was never part of the committed program. This module
+//! implements that as a logUp running-sum argument: for a verifier
+//! challenge `beta` (and a second challenge `alpha` used to fold a row's
+//! columns into one field element, its "fingerprint"), each table keeps
+//! an auxiliary column accumulating `multiplicity / (beta - fingerprint)`
+//! row by row. Two tables agree that one's rows are contained in the
+//! other's iff their final accumulated values are equal.
+
+use plonky2::field::types::Field;
+
+/// The two challenges needed to run a logUp argument over `(pc, opcode)`
+/// rows: `alpha` folds a row into a single field element (its
+/// "fingerprint"), `beta` is the running-sum's point of evaluation.
+/// Both should be drawn via Fiat-Shamir from a transcript that has
+/// already absorbed every participating table's trace commitment,
+/// before any of their STARK proofs are generated.
+#[derive(Clone, Copy, Debug)]
+pub struct CtlChallenge<F> {
+    pub alpha: F,
+    pub beta: F,
+}
+
+/// Folds a `(program_counter, opcode)` pair into the single field
+/// element a logUp argument over it operates on.
+pub fn fingerprint<F: Field>(program_counter: F, opcode: F, challenge: &CtlChallenge<F>) -> F {
+    program_counter + challenge.alpha * opcode
+}
+
+/// Computes the logUp running-sum column for one side of a CTL.
+///
+/// `rows` is a table's `(program_counter, opcode)` pairs in trace order,
+/// and `multiplicities[i]` is how many times `rows[i]` should count
+/// towards the sum (the 0/1 filter for a "looking" table such as
+/// `CpuStark`, or the actual reference count for a "looked" table such
+/// as `ProgramInstructionsStark`). Returns one running-sum value per
+/// row; the last entry is the side's total.
+pub fn ctl_running_sum<F: Field>(
+    rows: &[(F, F)],
+    multiplicities: &[F],
+    challenge: &CtlChallenge<F>,
+) -> Vec<F> {
+    assert_eq!(rows.len(), multiplicities.len());
+
+    let mut running_sum = F::ZERO;
+    rows.iter()
+        .zip(multiplicities.iter())
+        .map(|(&(pc, opcode), &multiplicity)| {
+            let denom = challenge.beta - fingerprint(pc, opcode, challenge);
+            running_sum += multiplicity * denom.inverse();
+            running_sum
+        })
+        .collect()
+}
+
+/// The shared transition constraint every CTL participant's auxiliary
+/// column must satisfy: `(z_next - z_local) * (beta - fingerprint) ==
+/// multiplicity`. Returns the constraint expression; the caller feeds
+/// it to a `ConstraintConsumer` gated as a transition constraint (it
+/// does not hold across the wraparound row).
+///
+/// Both the generator (`ctl_running_sum`/`ctl_running_sum_from_fingerprints`)
+/// and this constraint treat `z` as an *inclusive* prefix sum: `z[i]`
+/// already folds in row `i`'s own term, so `z[i+1] - z[i]` equals row
+/// `i+1`'s term, not row `i`'s. Callers must therefore pass the *next*
+/// row's fingerprint and multiplicity here, not the local row's —
+/// passing the local row's instead checks `z[i+1] - z[i] ==
+/// term[i]`, which the honest trace (built from the inclusive
+/// generator) does not satisfy.
+pub fn eval_ctl_transition<P: Copy + std::ops::Sub<Output = P> + std::ops::Mul<Output = P>>(
+    z_local: P,
+    z_next: P,
+    fingerprint: P,
+    beta: P,
+    multiplicity: P,
+) -> P {
+    (z_next - z_local) * (beta - fingerprint) - multiplicity
+}
+
+/// The first-row pin every CTL participant's auxiliary column must
+/// satisfy, complementing `eval_ctl_transition`: since `z` is an
+/// *inclusive* prefix sum, `z[0]` must already fold in row 0's own
+/// term, i.e. `z[0] * (beta - fingerprint[0]) == multiplicity[0]`.
+/// Without this pin the transition constraint alone only fixes every
+/// `z[i+1]` relative to `z[i]` — it never ties `z[0]` itself to row 0,
+/// so a prover could seed the running sum with any value at all.
+/// Returns the constraint expression; the caller feeds it to a
+/// `ConstraintConsumer` gated as a first-row constraint.
+pub fn eval_ctl_first_row<P: Copy + std::ops::Sub<Output = P> + std::ops::Mul<Output = P>>(
+    z_local: P,
+    fingerprint: P,
+    beta: P,
+    multiplicity: P,
+) -> P {
+    z_local * (beta - fingerprint) - multiplicity
+}
+
+/// Checks that a "looking" table's total agrees with a "looked" table's
+/// total, i.e. that the looking side's rows are indeed contained (with
+/// multiplicity) in the looked side. Also used to compare the two
+/// grand-product totals of a `MemoryCheckChallenge` argument, since the
+/// equality check is identical.
+pub fn verify_ctl<F: Field>(looking_total: F, looked_total: F) -> anyhow::Result<()> {
+    if looking_total == looked_total {
+        Ok(())
+    } else {
+        Err(anyhow::anyhow!(
+            "cross-table lookup failed: looking side total {:?} != looked side total {:?}",
+            looking_total,
+            looked_total,
+        ))
+    }
+}
+
+/// Folds an arbitrary-width row into a single field element using
+/// successive powers of `alpha`: `row[0] + alpha*row[1] + alpha^2*row[2]
+/// + ...`. Used for CTLs whose tuple is wider than `(pc, opcode)`, e.g.
+/// the `(address, value, timestamp)` tuple linking `CpuStark`'s `Lb`/
+/// `Sb` rows to `MemoryStark`.
+pub fn fingerprint_n<F: Field>(row: &[F], challenge: &CtlChallenge<F>) -> F {
+    let mut power = F::ONE;
+    let mut acc = F::ZERO;
+    for &value in row {
+        acc += power * value;
+        power *= challenge.alpha;
+    }
+    acc
+}
+
+/// Computes the logUp running-sum column for one side of a CTL whose
+/// rows have already been folded into fingerprints (see
+/// `fingerprint_n`), for tables whose looked-up tuple doesn't fit the
+/// `(pc, opcode)`-specific `ctl_running_sum` above.
+pub fn ctl_running_sum_from_fingerprints<F: Field>(
+    fingerprints: &[F],
+    multiplicities: &[F],
+    beta: F,
+) -> Vec<F> {
+    assert_eq!(fingerprints.len(), multiplicities.len());
+
+    let mut running_sum = F::ZERO;
+    fingerprints
+        .iter()
+        .zip(multiplicities.iter())
+        .map(|(&fingerprint, &multiplicity)| {
+            let denom = beta - fingerprint;
+            running_sum += multiplicity * denom.inverse();
+            running_sum
+        })
+        .collect()
+}
+
+/// Challenges for the memory offline-checking grand-product argument:
+/// `beta` folds an `(address, value, timestamp)` access tuple into one
+/// field element, `gamma` is the point that fingerprint is evaluated
+/// against.
+#[derive(Clone, Copy, Debug)]
+pub struct MemoryCheckChallenge<F> {
+    pub beta: F,
+    pub gamma: F,
+}
+
+/// `gamma - (address + beta*value + beta^2*timestamp)`.
+pub fn memory_fingerprint<F: Field>(
+    address: F,
+    value: F,
+    timestamp: F,
+    challenge: &MemoryCheckChallenge<F>,
+) -> F {
+    challenge.gamma - (address + challenge.beta * value + challenge.beta * challenge.beta * timestamp)
+}
+
+/// Running product of a sequence of fingerprints, used to check that
+/// the "write set + initial memory" and "read set + final memory"
+/// multisets are equal (see `MemoryStark`). Returns one running value
+/// per row; the last entry is the set's total.
+pub fn grand_product_running<F: Field>(fingerprints: &[F]) -> Vec<F> {
+    let mut running_product = F::ONE;
+    fingerprints
+        .iter()
+        .map(|&fingerprint| {
+            running_product *= fingerprint;
+            running_product
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::field::{
+        goldilocks_field::GoldilocksField,
+        types::Sample,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_ctl_matches_when_looking_rows_are_a_subset() {
+        type F = GoldilocksField;
+        let challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+
+        // "Looked" table: two distinct program rows, one of them (pc=5)
+        // executed twice and the other (pc=9) executed once.
+        let looked_rows = [(F::from_canonical_u64(5), F::from_canonical_u64(1)), (
+            F::from_canonical_u64(9),
+            F::from_canonical_u64(2),
+        )];
+        let looked_multiplicities = [F::from_canonical_u64(2), F::from_canonical_u64(1)];
+
+        // "Looking" table: the three executed steps, in execution order.
+        let looking_rows = [
+            (F::from_canonical_u64(5), F::from_canonical_u64(1)),
+            (F::from_canonical_u64(9), F::from_canonical_u64(2)),
+            (F::from_canonical_u64(5), F::from_canonical_u64(1)),
+        ];
+        let looking_multiplicities = [F::ONE; 3];
+
+        let looked_total = *ctl_running_sum(&looked_rows, &looked_multiplicities, &challenge)
+            .last()
+            .unwrap();
+        let looking_total = *ctl_running_sum(&looking_rows, &looking_multiplicities, &challenge)
+            .last()
+            .unwrap();
+
+        assert!(verify_ctl(looking_total, looked_total).is_ok());
+    }
+
+    #[test]
+    fn test_ctl_fails_on_unlisted_instruction() {
+        type F = GoldilocksField;
+        let challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+
+        let looked_rows = [(F::from_canonical_u64(5), F::from_canonical_u64(1))];
+        let looked_multiplicities = [F::ONE];
+
+        // Executes pc=9, which never appears in the program table.
+        let looking_rows = [(F::from_canonical_u64(9), F::from_canonical_u64(2))];
+        let looking_multiplicities = [F::ONE];
+
+        let looked_total = *ctl_running_sum(&looked_rows, &looked_multiplicities, &challenge)
+            .last()
+            .unwrap();
+        let looking_total = *ctl_running_sum(&looking_rows, &looking_multiplicities, &challenge)
+            .last()
+            .unwrap();
+
+        assert!(verify_ctl(looking_total, looked_total).is_err());
+    }
+
+    #[test]
+    fn test_memory_grand_product_matches_for_a_valid_access_sequence() {
+        type F = GoldilocksField;
+        let challenge = MemoryCheckChallenge::<F> {
+            beta: F::rand(),
+            gamma: F::rand(),
+        };
+
+        // Address 7 starts at 0 (virtual init, ts=0), is written to 9 at
+        // ts=2, then read back at ts=3, and a virtual final read restates
+        // that last access. As in `MemoryStark`, every real access reads
+        // the state just before it and writes its own resulting state.
+        let write_set = [
+            // virtual init
+            memory_fingerprint(F::from_canonical_u64(7), F::ZERO, F::ZERO, &challenge),
+            // the write's resulting state
+            memory_fingerprint(
+                F::from_canonical_u64(7),
+                F::from_canonical_u64(9),
+                F::from_canonical_u64(2),
+                &challenge,
+            ),
+            // the read's resulting state
+            memory_fingerprint(
+                F::from_canonical_u64(7),
+                F::from_canonical_u64(9),
+                F::from_canonical_u64(3),
+                &challenge,
+            ),
+        ];
+        let read_set = [
+            // the state just before the write
+            memory_fingerprint(F::from_canonical_u64(7), F::ZERO, F::ZERO, &challenge),
+            // the state just before the read
+            memory_fingerprint(
+                F::from_canonical_u64(7),
+                F::from_canonical_u64(9),
+                F::from_canonical_u64(2),
+                &challenge,
+            ),
+            // virtual final
+            memory_fingerprint(
+                F::from_canonical_u64(7),
+                F::from_canonical_u64(9),
+                F::from_canonical_u64(3),
+                &challenge,
+            ),
+        ];
+
+        let write_total = *grand_product_running(&write_set)
+            .last()
+            .unwrap();
+        let read_total = *grand_product_running(&read_set)
+            .last()
+            .unwrap();
+
+        assert!(verify_ctl(write_total, read_total).is_ok());
+    }
+
+    #[test]
+    fn test_memory_grand_product_fails_on_tampered_read() {
+        type F = GoldilocksField;
+        let challenge = MemoryCheckChallenge::<F> {
+            beta: F::rand(),
+            gamma: F::rand(),
+        };
+
+        let write_set = [memory_fingerprint(
+            F::from_canonical_u64(7),
+            F::from_canonical_u64(9),
+            F::ZERO,
+            &challenge,
+        )];
+        // A read claiming a value (11) that was never written.
+        let read_set = [memory_fingerprint(
+            F::from_canonical_u64(7),
+            F::from_canonical_u64(11),
+            F::ONE,
+            &challenge,
+        )];
+
+        let write_total = *grand_product_running(&write_set)
+            .last()
+            .unwrap();
+        let read_total = *grand_product_running(&read_set)
+            .last()
+            .unwrap();
+
+        assert!(verify_ctl(write_total, read_total).is_err());
+    }
+}
@@ -0,0 +1,130 @@
+//! Lasso-style lookup subtables for byte-oriented ALU/shift instructions.
+//!
+//! Constraining `Mul`, `Shl`, and `Shr` directly over the field needs
+//! witness columns (quotient/remainder, in `stark_cpu`) whose range is
+//! never actually checked — nothing stops a dishonest prover from
+//! picking an out-of-range quotient that still balances the
+//! arithmetic. The fix used here is the standard Lasso trick: instead
+//! of constraining the full 8-bit operation directly, decompose it
+//! into small chunks (nibbles) and look each chunk pair up in a fully
+//! materialized subtable (so every possible input to the chunk
+//! operation is present, with no gaps a dishonest prover could exploit
+//! by construction), then recombine the looked-up chunk outputs back
+//! into the full-width result. `stark_lookup::LookupStark` proves the
+//! subtable side of that lookup; this module is the data-level half —
+//! the subtable's rows and the decomposition/recombination a new
+//! instruction needs to register its own subtable.
+
+/// A Lasso-style lookup subtable: a fully materialized table of
+/// `(input_a, input_b, output)` rows, plus the glue an ALU instruction
+/// needs to decompose a full-width operation into queries against this
+/// table and recombine the results. `rows()` must return every input
+/// pair in the subtable's domain exactly once (so the table is
+/// self-contained and a lookup against it can never fail to find a
+/// match), and its length must already be a power of two, so
+/// `LookupStark` only pads it when it is shorter than
+/// `trace_util::MIN_TRACE_LEN`.
+pub trait LookupTable: Clone + Copy {
+    /// Every `(input_a, input_b, output)` row in the subtable's domain.
+    fn rows() -> Vec<(u8, u8, u8)>;
+
+    /// Splits a full-width `(a, b)` operand pair into the chunk pairs
+    /// this subtable should be queried with, in the order `recombine`
+    /// expects its outputs back in.
+    fn decompose(a: u8, b: u8) -> Vec<(u8, u8)>;
+
+    /// Folds this subtable's per-chunk outputs (in `decompose`'s order)
+    /// back into the full-width result.
+    fn recombine(chunk_outputs: &[u8]) -> u16;
+}
+
+/// Multiplies two bytes by decomposing each into a low/high nibble and
+/// looking up all four nibble-pair products: `a*b == lo*lo + (lo*hi +
+/// hi*lo)*16 + hi*hi*256`.
+#[derive(Clone, Copy, Debug)]
+pub struct MulLookupTable;
+
+impl LookupTable for MulLookupTable {
+    fn rows() -> Vec<(u8, u8, u8)> {
+        (0..16u8)
+            .flat_map(|a| (0..16u8).map(move |b| (a, b, a * b)))
+            .collect()
+    }
+
+    fn decompose(a: u8, b: u8) -> Vec<(u8, u8)> {
+        let (a_lo, a_hi) = (a & 0xF, a >> 4);
+        let (b_lo, b_hi) = (b & 0xF, b >> 4);
+        vec![(a_lo, b_lo), (a_lo, b_hi), (a_hi, b_lo), (a_hi, b_hi)]
+    }
+
+    fn recombine(chunk_outputs: &[u8]) -> u16 {
+        let [lo_lo, lo_hi, hi_lo, hi_hi] = chunk_outputs else {
+            panic!("MulLookupTable::recombine expects exactly 4 chunk outputs");
+        };
+        *lo_lo as u16 + (*lo_hi as u16 + *hi_lo as u16) * 16 + *hi_hi as u16 * 256
+    }
+}
+
+/// Range-checks a byte by looking it up in the identity table over
+/// `0..=255`: the lookup only succeeds if the value is actually a
+/// valid `u8`, with no decomposition needed since the full domain fits
+/// in one subtable.
+#[derive(Clone, Copy, Debug)]
+pub struct RangeCheckU8LookupTable;
+
+impl LookupTable for RangeCheckU8LookupTable {
+    fn rows() -> Vec<(u8, u8, u8)> {
+        (0..=255u8)
+            .map(|value| (value, 0, value))
+            .collect()
+    }
+
+    fn decompose(a: u8, _b: u8) -> Vec<(u8, u8)> {
+        vec![(a, 0)]
+    }
+
+    fn recombine(chunk_outputs: &[u8]) -> u16 {
+        chunk_outputs[0] as u16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn mul_lookup_table_rows_cover_every_nibble_pair() {
+        let rows = MulLookupTable::rows();
+        assert_eq!(rows.len(), 256);
+        assert!(rows
+            .iter()
+            .all(|&(a, b, out)| out == a * b));
+    }
+
+    #[test]
+    fn mul_decompose_recombine_round_trips_for_every_byte_pair() {
+        for a in 0..=255u8 {
+            for b in 0..=255u8 {
+                let chunks = MulLookupTable::decompose(a, b);
+                let outputs: Vec<u8> = chunks
+                    .iter()
+                    .map(|&(ca, cb)| ca * cb)
+                    .collect();
+                let recombined = MulLookupTable::recombine(&outputs);
+                assert_eq!(recombined, a as u16 * b as u16);
+            }
+        }
+    }
+
+    #[test]
+    fn range_check_decompose_recombine_is_the_identity() {
+        for value in 0..=255u8 {
+            let chunks = RangeCheckU8LookupTable::decompose(value, 0);
+            let outputs: Vec<u8> = chunks
+                .iter()
+                .map(|&(v, _)| v)
+                .collect();
+            assert_eq!(RangeCheckU8LookupTable::recombine(&outputs), value as u16);
+        }
+    }
+}
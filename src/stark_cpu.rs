@@ -0,0 +1,1208 @@
+//! This file is an encoding of the actual *running* process trace of
+//! the VM, complementing `ProgramInstructionsStark` which only commits
+//! to the static program listing. Where the program table has one row
+//! per distinct instruction in `Program::code`, this table has one row
+//! per instruction actually *executed*, in execution order, including
+//! repeats from loops and skips from jumps.
+
+use core::marker::PhantomData;
+use plonky2::{
+    field::{
+        extension::{
+            Extendable,
+            FieldExtension,
+        },
+        packed::PackedField,
+        polynomial::PolynomialValues,
+    },
+    hash::hash_types::RichField,
+    iop::ext_target::ExtensionTarget,
+    plonk::circuit_builder::CircuitBuilder,
+};
+use starky::{
+    constraint_consumer::{
+        ConstraintConsumer,
+        RecursiveConstraintConsumer,
+    },
+    evaluation_frame::{
+        StarkEvaluationFrame,
+        StarkFrame,
+    },
+    stark::Stark,
+    util::trace_rows_to_poly_values,
+};
+
+use crate::{
+    cross_table_lookup::CtlChallenge,
+    vm_specs::{
+        Instruction,
+        Program,
+        Register,
+        REGISTER_COUNT,
+    },
+};
+
+// Table description. `OPCODE` is the 11-wide one-hot encoding produced
+// by `Instruction::one_hot_encode_and_apply`. Columns, in order:
+// PC | OPCODE | REG_BEFORE | REG_AFTER | DST_SEL | SRC_SEL | ADD_CARRY |
+// SUB_BORROW | MUL_QUOTIENT | DIV_REMAINDER | DIV_SRC_INV |
+// DIV_SRC_IS_ZERO | SHIFT_AMOUNT_ONE_HOT | SHIFT_MULTIPLIER |
+// SHIFT_QUOTIENT | SHIFT_REMAINDER | SHIFT_SRC_QUOTIENT | JUMP_TARGET |
+// TESTED_REG | TESTED_REG_INV | TESTED_REG_IS_ZERO | MEM_ADDR |
+// MEM_VALUE | NEXT_PC | IS_EXECUTED | OPCODE_VALUE | CTL_Z |
+// STEP_TIMESTAMP | MEM_CTL_Z
+//
+// Column layout (indices below are used throughout this file):
+const PC: usize = 0;
+const OPCODE_START: usize = PC + 1;
+const OPCODE_WIDTH: usize = 11;
+const REG_BEFORE_START: usize = OPCODE_START + OPCODE_WIDTH;
+const REG_AFTER_START: usize = REG_BEFORE_START + REGISTER_COUNT;
+// `dst`/`src` register selectors: 0 selects R0, 1 selects R1.
+const DST_SEL: usize = REG_AFTER_START + REGISTER_COUNT;
+const SRC_SEL: usize = DST_SEL + 1;
+// Add/Sub overflow handling: `dst + src = result + carry * 256`
+// (Add) or `dst - src + borrow * 256 = result` (Sub).
+const ADD_CARRY: usize = SRC_SEL + 1;
+const SUB_BORROW: usize = ADD_CARRY + 1;
+// Mul overflow handling: `dst * src = result + quotient * 256`.
+// Note: this constrains the arithmetic but not that `quotient` itself
+// is a valid byte; `lookup_table`/`stark_lookup` provide a Lasso-style
+// lookup argument new instructions can use to range-check witness
+// columns like this one instead.
+const MUL_QUOTIENT: usize = SUB_BORROW + 1;
+// Div: `dst_before = dst_after * src_before + remainder` when
+// `src_before != 0` (remainder unchecked beyond this, the same
+// `lookup_table`-deferred byte-range leniency as `MUL_QUOTIENT` above);
+// when `src_before == 0` the VM defines the result as 0 instead, which
+// `DIV_SRC_IS_ZERO`/`DIV_SRC_INV` (an is-zero gadget, mirroring
+// `TESTED_REG`/`TESTED_REG_INV` below) select between.
+const DIV_REMAINDER: usize = MUL_QUOTIENT + 1;
+const DIV_SRC_INV: usize = DIV_REMAINDER + 1;
+const DIV_SRC_IS_ZERO: usize = DIV_SRC_INV + 1;
+// Shl/Shr: shift amount (src register, taken mod 8) one-hot encoded,
+// plus the resulting power-of-two multiplier it selects.
+const SHIFT_AMOUNT_ONE_HOT_START: usize = DIV_SRC_IS_ZERO + 1;
+const SHIFT_AMOUNT_ONE_HOT_WIDTH: usize = 8;
+const SHIFT_MULTIPLIER: usize = SHIFT_AMOUNT_ONE_HOT_START + SHIFT_AMOUNT_ONE_HOT_WIDTH;
+// Shl overflow (`dst * multiplier = result + quotient * 256`) and Shr
+// remainder (`dst = result * multiplier + remainder`), mirroring the
+// Add/Sub/Mul overflow handling above.
+const SHIFT_QUOTIENT: usize = SHIFT_MULTIPLIER + 1;
+const SHIFT_REMAINDER: usize = SHIFT_QUOTIENT + 1;
+// `src_before`'s quotient by 8: the one-hot above only encodes
+// `src_before mod 8`, so this lets the constraint recover `src_before`
+// itself (`src_before == 8 * SHIFT_SRC_QUOTIENT + shift_amount`) instead
+// of wrongly requiring `src_before < 8`.
+const SHIFT_SRC_QUOTIENT: usize = SHIFT_REMAINDER + 1;
+// Jz/Jnz: jump target and the zero-test gadget for the tested register.
+const JUMP_TARGET: usize = SHIFT_SRC_QUOTIENT + 1;
+const TESTED_REG: usize = JUMP_TARGET + 1;
+const TESTED_REG_INV: usize = TESTED_REG + 1;
+const TESTED_REG_IS_ZERO: usize = TESTED_REG_INV + 1;
+// Lb/Sb: the memory address and value touched by the instruction, tied
+// to `MemoryStark` by the `MEM_CTL_Z` running sum below.
+const MEM_ADDR: usize = TESTED_REG_IS_ZERO + 1;
+const MEM_VALUE: usize = MEM_ADDR + 1;
+// Control flow and padding.
+const NEXT_PC: usize = MEM_VALUE + 1;
+const IS_EXECUTED: usize = NEXT_PC + 1;
+// The raw (non-one-hot) opcode byte and the logUp running sum tying
+// this table's executed `(pc, opcode)` rows into
+// `ProgramInstructionsStark`'s listing (see `cross_table_lookup`).
+const OPCODE_VALUE: usize = IS_EXECUTED + 1;
+const CTL_Z: usize = OPCODE_VALUE + 1;
+// This row's global step index (1-based, so it never collides with the
+// `timestamp == 0` virtual rows `MemoryStark` seeds each address block
+// with), and the logUp running sum tying `Lb`/`Sb` rows to the matching
+// access in `MemoryStark`.
+const STEP_TIMESTAMP: usize = CTL_Z + 1;
+const MEM_CTL_Z: usize = STEP_TIMESTAMP + 1;
+
+const NUMBER_OF_COLS: usize = MEM_CTL_Z + 1;
+// Public inputs 0/1 carry the program-listing CTL challenges (`beta`,
+// `alpha`); 2/3 carry the memory CTL challenges.
+const PROGRAM_CTL_BETA: usize = 0;
+const PROGRAM_CTL_ALPHA: usize = 1;
+const MEM_CTL_BETA: usize = 2;
+const MEM_CTL_ALPHA: usize = 3;
+const PUBLIC_INPUTS: usize = 4;
+
+fn opcode_col(opcode: u8) -> usize {
+    OPCODE_START + opcode as usize
+}
+
+// Small helpers shared by `eval_ext_circuit`, mirroring the packed-field
+// expressions used throughout `eval_packed_generic`.
+
+fn ext_select<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    sel: ExtensionTarget<D>,
+    a: ExtensionTarget<D>,
+    b: ExtensionTarget<D>,
+) -> ExtensionTarget<D> {
+    let diff = builder.sub_extension(b, a);
+    builder.mul_add_extension(sel, diff, a)
+}
+
+/// `x * (1 - x)`, up to sign (`x * x - x`).
+fn ext_bool_check<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    x: ExtensionTarget<D>,
+) -> ExtensionTarget<D> {
+    builder.mul_sub_extension(x, x, x)
+}
+
+fn ext_mul3<F: RichField + Extendable<D>, const D: usize>(
+    builder: &mut CircuitBuilder<F, D>,
+    a: ExtensionTarget<D>,
+    b: ExtensionTarget<D>,
+    c: ExtensionTarget<D>,
+) -> ExtensionTarget<D> {
+    let ab = builder.mul_extension(a, b);
+    builder.mul_extension(ab, c)
+}
+
+#[derive(Clone, Copy)]
+pub struct CpuStark<F, const D: usize> {
+    pub _f: PhantomData<F>,
+}
+
+impl<F, const D: usize> CpuStark<F, D>
+where
+    F: RichField + Extendable<D>,
+{
+    pub fn new() -> Self {
+        Self { _f: PhantomData }
+    }
+
+    pub fn generate_trace(
+        prog: &Program,
+        ctl_challenge: CtlChallenge<F>,
+        mem_ctl_challenge: CtlChallenge<F>,
+    ) -> Vec<PolynomialValues<F>>
+    where
+        F: RichField,
+    {
+        let steps = prog.execute();
+
+        let mut trace = steps
+            .iter()
+            .enumerate()
+            .map(|(step_index, step)| {
+                let mut row = [F::ZERO; NUMBER_OF_COLS];
+                row[PC] = F::from_canonical_u8(step.pc);
+                row[IS_EXECUTED] = F::ONE;
+                row[NEXT_PC] = F::from_canonical_u8(step.next_pc);
+                row[OPCODE_VALUE] = F::from_canonical_u8(step.instruction.get_opcode());
+                // 1-based so it never collides with `MemoryStark`'s
+                // `timestamp == 0` virtual init rows.
+                row[STEP_TIMESTAMP] = F::from_canonical_u64(step_index as u64 + 1);
+
+                let one_hot = step
+                    .instruction
+                    .one_hot_encode_and_apply::<F>();
+                row[OPCODE_START..OPCODE_START + OPCODE_WIDTH].copy_from_slice(&one_hot);
+
+                for i in 0..REGISTER_COUNT {
+                    row[REG_BEFORE_START + i] = F::from_canonical_u8(step.registers_before[i]);
+                    row[REG_AFTER_START + i] = F::from_canonical_u8(step.registers_after[i]);
+                }
+
+                let reg_sel = |reg: Register| -> u8 { usize::from(reg) as u8 };
+                let fill_dst_src = |row: &mut [F; NUMBER_OF_COLS], dst: Register, src: Register| {
+                    row[DST_SEL] = F::from_canonical_u8(reg_sel(dst));
+                    row[SRC_SEL] = F::from_canonical_u8(reg_sel(src));
+                };
+
+                match &step.instruction {
+                    Instruction::Add(dst, src) => {
+                        fill_dst_src(&mut row, *dst, *src);
+                        let a = step.registers_before[usize::from(*dst)] as u16;
+                        let b = step.registers_before[usize::from(*src)] as u16;
+                        let sum = a + b;
+                        row[ADD_CARRY] = if sum >= 256 { F::ONE } else { F::ZERO };
+                    }
+                    Instruction::Sub(dst, src) => {
+                        fill_dst_src(&mut row, *dst, *src);
+                        let a = step.registers_before[usize::from(*dst)] as i16;
+                        let b = step.registers_before[usize::from(*src)] as i16;
+                        row[SUB_BORROW] = if a - b < 0 { F::ONE } else { F::ZERO };
+                    }
+                    Instruction::Mul(dst, src) => {
+                        fill_dst_src(&mut row, *dst, *src);
+                        let a = step.registers_before[usize::from(*dst)] as u16;
+                        let b = step.registers_before[usize::from(*src)] as u16;
+                        let product = a * b;
+                        row[MUL_QUOTIENT] = F::from_canonical_u16(product / 256);
+                    }
+                    Instruction::Div(dst, src) => {
+                        fill_dst_src(&mut row, *dst, *src);
+                        let divisor = step.registers_before[usize::from(*src)];
+                        if divisor == 0 {
+                            row[DIV_SRC_IS_ZERO] = F::ONE;
+                        } else {
+                            row[DIV_SRC_INV] = F::from_canonical_u8(divisor).inverse();
+                            let dividend = step.registers_before[usize::from(*dst)];
+                            row[DIV_REMAINDER] = F::from_canonical_u8(dividend % divisor);
+                        }
+                    }
+                    Instruction::Shl(dst, src) => {
+                        fill_dst_src(&mut row, *dst, *src);
+                        let src_before_val = step.registers_before[usize::from(*src)];
+                        let shift = (src_before_val & 0x7) as usize;
+                        row[SHIFT_AMOUNT_ONE_HOT_START + shift] = F::ONE;
+                        row[SHIFT_SRC_QUOTIENT] = F::from_canonical_u8(src_before_val / 8);
+                        row[SHIFT_MULTIPLIER] = F::from_canonical_u16(1u16 << shift);
+                        let product = step.registers_before[usize::from(*dst)] as u16
+                            * (1u16 << shift);
+                        row[SHIFT_QUOTIENT] = F::from_canonical_u16(product / 256);
+                    }
+                    Instruction::Shr(dst, src) => {
+                        fill_dst_src(&mut row, *dst, *src);
+                        let src_before_val = step.registers_before[usize::from(*src)];
+                        let shift = (src_before_val & 0x7) as usize;
+                        row[SHIFT_AMOUNT_ONE_HOT_START + shift] = F::ONE;
+                        row[SHIFT_SRC_QUOTIENT] = F::from_canonical_u8(src_before_val / 8);
+                        let multiplier = 1u16 << shift;
+                        row[SHIFT_MULTIPLIER] = F::from_canonical_u16(multiplier);
+                        let dividend = step.registers_before[usize::from(*dst)] as u16;
+                        row[SHIFT_REMAINDER] = F::from_canonical_u16(dividend % multiplier);
+                    }
+                    Instruction::Jz(reg, loc) | Instruction::Jnz(reg, loc) => {
+                        row[DST_SEL] = F::from_canonical_u8(reg_sel(*reg));
+                        let tested = step.registers_before[usize::from(*reg)];
+                        row[TESTED_REG] = F::from_canonical_u8(tested);
+                        if tested == 0 {
+                            row[TESTED_REG_IS_ZERO] = F::ONE;
+                        } else {
+                            row[TESTED_REG_INV] = F::from_canonical_u8(tested)
+                                .inverse();
+                        }
+                        row[JUMP_TARGET] = F::from_canonical_u8(loc.0);
+                    }
+                    Instruction::Lb(dst, addr) => {
+                        row[DST_SEL] = F::from_canonical_u8(reg_sel(*dst));
+                        row[MEM_ADDR] = F::from_canonical_u8(addr.0);
+                        row[MEM_VALUE] =
+                            F::from_canonical_u8(step.memory_value.unwrap_or_default());
+                    }
+                    Instruction::Sb(src, addr) => {
+                        row[SRC_SEL] = F::from_canonical_u8(reg_sel(*src));
+                        row[MEM_ADDR] = F::from_canonical_u8(addr.0);
+                        row[MEM_VALUE] =
+                            F::from_canonical_u8(step.memory_value.unwrap_or_default());
+                    }
+                    Instruction::Halt => {}
+                }
+
+                row
+            })
+            .collect::<Vec<[F; NUMBER_OF_COLS]>>();
+
+        // Every executed row is "looked up" exactly once against the
+        // program listing, hence a multiplicity of 1 per real row.
+        let ctl_z = crate::cross_table_lookup::ctl_running_sum(
+            &trace
+                .iter()
+                .map(|row| (row[PC], row[OPCODE_VALUE]))
+                .collect::<Vec<_>>(),
+            &trace
+                .iter()
+                .map(|row| row[IS_EXECUTED])
+                .collect::<Vec<_>>(),
+            &ctl_challenge,
+        );
+        for (row, &z) in trace
+            .iter_mut()
+            .zip(ctl_z.iter())
+        {
+            row[CTL_Z] = z;
+        }
+
+        // `Lb`/`Sb` rows are looked up against `MemoryStark`'s access
+        // log by their `(address, value, timestamp)` fingerprint.
+        let mem_multiplicity = |row: &[F; NUMBER_OF_COLS]| {
+            row[opcode_col(8)] + row[opcode_col(9)]
+        };
+        let mem_ctl_z = crate::cross_table_lookup::ctl_running_sum_from_fingerprints(
+            &trace
+                .iter()
+                .map(|row| {
+                    crate::cross_table_lookup::fingerprint_n(
+                        &[row[MEM_ADDR], row[MEM_VALUE], row[STEP_TIMESTAMP]],
+                        &mem_ctl_challenge,
+                    )
+                })
+                .collect::<Vec<_>>(),
+            &trace
+                .iter()
+                .map(mem_multiplicity)
+                .collect::<Vec<_>>(),
+            mem_ctl_challenge.beta,
+        );
+        for (row, &z) in trace
+            .iter_mut()
+            .zip(mem_ctl_z.iter())
+        {
+            row[MEM_CTL_Z] = z;
+        }
+
+        // Pad to a power of two; padding rows carry zero multiplicity
+        // (`IS_EXECUTED == 0`), so both `CTL_Z` columns simply hold at
+        // their last real value.
+        let last_z = trace
+            .last()
+            .map(|row| row[CTL_Z])
+            .unwrap_or(F::ZERO);
+        let last_mem_z = trace
+            .last()
+            .map(|row| row[MEM_CTL_Z])
+            .unwrap_or(F::ZERO);
+        let pow2_len = crate::trace_util::padded_trace_len(trace.len());
+        let mut pad_row = [F::ZERO; NUMBER_OF_COLS];
+        pad_row[CTL_Z] = last_z;
+        pad_row[MEM_CTL_Z] = last_mem_z;
+        trace.resize(pow2_len, pad_row);
+
+        trace_rows_to_poly_values(trace)
+    }
+}
+
+impl<F, const D: usize> Stark<F, D> for CpuStark<F, D>
+where
+    F: RichField + Extendable<D>,
+{
+    type EvaluationFrame<FE, P, const D2: usize> = StarkFrame<P, P::Scalar, NUMBER_OF_COLS, PUBLIC_INPUTS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+    type EvaluationFrameTarget = StarkFrame<
+        ExtensionTarget<D>,
+        ExtensionTarget<D>,
+        NUMBER_OF_COLS,
+        PUBLIC_INPUTS,
+    >;
+
+    const COLUMNS: usize = NUMBER_OF_COLS;
+    const PUBLIC_INPUTS: usize = PUBLIC_INPUTS;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values = vars.get_local_values();
+        let next_values = vars.get_next_values();
+        let public_inputs = vars.get_public_inputs();
+
+        let is_executed = local_values[IS_EXECUTED];
+        yield_constr.constraint(is_executed * (P::ONES - is_executed));
+
+        let opcode = |op: u8| local_values[opcode_col(op)];
+        // Exactly one opcode selector is active per executed row. On
+        // padding rows (`is_executed == 0`) every selector is free to
+        // be zero, so we only gate this when the row is executed.
+        let one_hot_sum = (0..OPCODE_WIDTH)
+            .map(|i| local_values[OPCODE_START + i])
+            .fold(P::ZEROS, |acc, x| acc + x);
+        yield_constr.constraint(is_executed * (one_hot_sum - P::ONES));
+
+        let pc = local_values[PC];
+        let next_pc = local_values[NEXT_PC];
+        let dst_sel = local_values[DST_SEL];
+        let src_sel = local_values[SRC_SEL];
+
+        // Select between R0/R1 columns using the boolean `dst_sel`/
+        // `src_sel` witnesses: REGISTER_COUNT is fixed at 2, so a
+        // single selector bit per operand suffices.
+        yield_constr.constraint(is_executed * dst_sel * (P::ONES - dst_sel));
+        yield_constr.constraint(is_executed * src_sel * (P::ONES - src_sel));
+
+        let reg_before_0 = local_values[REG_BEFORE_START];
+        let reg_before_1 = local_values[REG_BEFORE_START + 1];
+        let reg_after_0 = local_values[REG_AFTER_START];
+        let reg_after_1 = local_values[REG_AFTER_START + 1];
+
+        let select = |sel: P, a: P, b: P| a + sel * (b - a);
+        let dst_before = select(dst_sel, reg_before_0, reg_before_1);
+        let src_before = select(src_sel, reg_before_0, reg_before_1);
+        let dst_after = select(dst_sel, reg_after_0, reg_after_1);
+
+        // The non-destination register is untouched by the instruction.
+        let other_before = select(dst_sel, reg_before_1, reg_before_0);
+        let other_after = select(dst_sel, reg_after_1, reg_after_0);
+        let touches_registers = opcode(0)
+            + opcode(1)
+            + opcode(2)
+            + opcode(3)
+            + opcode(4)
+            + opcode(5)
+            + opcode(8);
+        yield_constr.constraint(is_executed * touches_registers * (other_after - other_before));
+
+        // Add: dst_after + carry * 256 == dst_before + src_before
+        let carry = local_values[ADD_CARRY];
+        yield_constr.constraint(is_executed * carry * (P::ONES - carry));
+        yield_constr.constraint(
+            is_executed
+                * opcode(0)
+                * (dst_after + carry * P::Scalar::from_canonical_u16(256) - dst_before
+                    - src_before),
+        );
+
+        // Sub: dst_before - src_before + borrow * 256 == dst_after
+        let borrow = local_values[SUB_BORROW];
+        yield_constr.constraint(is_executed * borrow * (P::ONES - borrow));
+        yield_constr.constraint(
+            is_executed
+                * opcode(1)
+                * (dst_before - src_before + borrow * P::Scalar::from_canonical_u16(256)
+                    - dst_after),
+        );
+
+        // Mul: dst_before * src_before == dst_after + quotient * 256
+        let quotient = local_values[MUL_QUOTIENT];
+        yield_constr.constraint(
+            is_executed
+                * opcode(2)
+                * (dst_before * src_before
+                    - dst_after
+                    - quotient * P::Scalar::from_canonical_u16(256)),
+        );
+
+        // Div: dst_after == 0 when src_before == 0 (the VM's defined
+        // behavior for division by zero), else dst_before ==
+        // dst_after * src_before + remainder. `div_is_zero` is an
+        // is-zero gadget over `src_before`, mirroring the Jz/Jnz one
+        // below.
+        let is_div = opcode(3);
+        let div_is_zero = local_values[DIV_SRC_IS_ZERO];
+        let div_src_inv = local_values[DIV_SRC_INV];
+        let div_remainder = local_values[DIV_REMAINDER];
+        yield_constr.constraint(is_executed * is_div * div_is_zero * src_before);
+        yield_constr.constraint(
+            is_executed * is_div * ((P::ONES - div_is_zero) - src_before * div_src_inv),
+        );
+        yield_constr.constraint(is_executed * is_div * div_is_zero * dst_after);
+        // `remainder < src_before` is NOT checked anywhere: `lookup_table`
+        // has no Div subtable yet, so nothing currently stops a
+        // dishonest prover from picking a `div_remainder` that's out of
+        // range but still balances this identity. Wiring a subtable in
+        // for this (and for `SHIFT_REMAINDER` below) is tracked as
+        // future work, same as the byte-range gap on `MUL_QUOTIENT`.
+        yield_constr.constraint(
+            is_executed
+                * is_div
+                * (P::ONES - div_is_zero)
+                * (dst_before - dst_after * src_before - div_remainder),
+        );
+
+        // Shl/Shr: the shift amount (src register, mod 8) is one-hot
+        // decomposed so its power-of-two multiplier can be read off
+        // without a lookup table.
+        let shift_one_hot: Vec<P> = (0..SHIFT_AMOUNT_ONE_HOT_WIDTH)
+            .map(|i| local_values[SHIFT_AMOUNT_ONE_HOT_START + i])
+            .collect();
+        let is_shift = opcode(4) + opcode(5);
+        let shift_one_hot_sum = shift_one_hot
+            .iter()
+            .fold(P::ZEROS, |acc, &x| acc + x);
+        yield_constr.constraint(is_executed * is_shift * (shift_one_hot_sum - P::ONES));
+        let shift_amount_from_one_hot = shift_one_hot
+            .iter()
+            .enumerate()
+            .fold(P::ZEROS, |acc, (i, &x)| {
+                acc + x * P::Scalar::from_canonical_u64(i as u64)
+            });
+        // `shift_amount_from_one_hot` only ever encodes `src_before mod
+        // 8`, so checking it directly against `src_before` (rather than
+        // via `SHIFT_SRC_QUOTIENT`) would wrongly reject any `src`
+        // register value of 8 or more.
+        let shift_src_quotient = local_values[SHIFT_SRC_QUOTIENT];
+        yield_constr.constraint(
+            is_executed
+                * is_shift
+                * (src_before
+                    - shift_amount_from_one_hot
+                    - shift_src_quotient * P::Scalar::from_canonical_u16(8)),
+        );
+        let multiplier = local_values[SHIFT_MULTIPLIER];
+        let multiplier_from_one_hot = shift_one_hot
+            .iter()
+            .enumerate()
+            .fold(P::ZEROS, |acc, (i, &x)| {
+                acc + x * P::Scalar::from_canonical_u64(1u64 << i)
+            });
+        yield_constr.constraint(is_executed * is_shift * (multiplier - multiplier_from_one_hot));
+        // Shl: dst_before * multiplier == dst_after + shift_quotient * 256.
+        let shift_quotient = local_values[SHIFT_QUOTIENT];
+        yield_constr.constraint(
+            is_executed
+                * opcode(4)
+                * (dst_before * multiplier
+                    - dst_after
+                    - shift_quotient * P::Scalar::from_canonical_u16(256)),
+        );
+        // Shr: dst_before == dst_after * multiplier + shift_remainder.
+        // `shift_remainder < multiplier` is NOT checked anywhere, same
+        // unwired gap as `DIV_REMAINDER`/`MUL_QUOTIENT`/`SHIFT_QUOTIENT`
+        // above — `lookup_table` has no Shl/Shr subtable yet either.
+        let shift_remainder = local_values[SHIFT_REMAINDER];
+        yield_constr.constraint(
+            is_executed
+                * opcode(5)
+                * (dst_before - dst_after * multiplier - shift_remainder),
+        );
+
+        // Jz/Jnz: the tested-register zero gadget.
+        let tested = local_values[TESTED_REG];
+        let tested_inv = local_values[TESTED_REG_INV];
+        let tested_is_zero = local_values[TESTED_REG_IS_ZERO];
+        let is_jump = opcode(6) + opcode(7);
+        yield_constr.constraint(is_executed * is_jump * (tested_is_zero * tested));
+        yield_constr.constraint(
+            is_executed * is_jump * (P::ONES - tested_is_zero - tested * tested_inv),
+        );
+
+        let target = local_values[JUMP_TARGET];
+        let fallthrough = pc + P::ONES;
+        // Jz: next_pc == target when tested == 0, else next_pc == pc + 1.
+        yield_constr.constraint(
+            is_executed
+                * opcode(6)
+                * (next_pc
+                    - (tested_is_zero * target + (P::ONES - tested_is_zero) * fallthrough)),
+        );
+        // Jnz: next_pc == target when tested != 0, else next_pc == pc + 1.
+        yield_constr.constraint(
+            is_executed
+                * opcode(7)
+                * (next_pc
+                    - ((P::ONES - tested_is_zero) * target + tested_is_zero * fallthrough)),
+        );
+
+        // Halt freezes pc and the whole register file.
+        yield_constr.constraint(is_executed * opcode(10) * (next_pc - pc));
+        yield_constr.constraint(is_executed * opcode(10) * (reg_after_0 - reg_before_0));
+        yield_constr.constraint(is_executed * opcode(10) * (reg_after_1 - reg_before_1));
+
+        // Every other (non-jump, non-halt) instruction simply advances
+        // the program counter by one.
+        let falls_through = P::ONES - is_jump - opcode(10);
+        yield_constr.constraint(is_executed * falls_through * (next_pc - fallthrough));
+
+        // Lb: dst_after == mem_value.
+        let mem_value = local_values[MEM_VALUE];
+        yield_constr.constraint(is_executed * opcode(8) * (dst_after - mem_value));
+        // Sb: mem_value == src_before (the memory STARK, added
+        // separately, is what actually proves the write landed).
+        yield_constr.constraint(is_executed * opcode(9) * (mem_value - src_before));
+
+        // Inter-row continuity: the next executed row must pick up
+        // exactly where this one left off — its `pc` is this row's
+        // `NEXT_PC`, and its register file starts out as this row's
+        // ending register file. Gated by `next.IS_EXECUTED` so the
+        // boundary into padding is left unconstrained.
+        let next_is_executed = next_values[IS_EXECUTED];
+        yield_constr.constraint_transition(next_is_executed * (next_values[PC] - next_pc));
+        yield_constr.constraint_transition(
+            next_is_executed * (next_values[REG_BEFORE_START] - reg_after_0),
+        );
+        yield_constr.constraint_transition(
+            next_is_executed * (next_values[REG_BEFORE_START + 1] - reg_after_1),
+        );
+
+        // CTL running-sum transition, tying this table's executed
+        // `(pc, opcode)` rows into `ProgramInstructionsStark`'s
+        // listing. See `cross_table_lookup`. `CTL_Z` is an inclusive
+        // prefix sum, so the term folded in between `local` and `next`
+        // is `next`'s own fingerprint/`IS_EXECUTED`, not `local`'s.
+        let beta = public_inputs[PROGRAM_CTL_BETA];
+        let alpha = public_inputs[PROGRAM_CTL_ALPHA];
+        let next_fingerprint = next_values[PC] + next_values[OPCODE_VALUE] * alpha;
+        yield_constr.constraint_transition(crate::cross_table_lookup::eval_ctl_transition(
+            local_values[CTL_Z],
+            next_values[CTL_Z],
+            next_fingerprint,
+            P::ONES * beta,
+            next_values[IS_EXECUTED],
+        ));
+
+        // First-row pin: the transition constraint above only fixes
+        // `CTL_Z` relative to its own previous row, never to row 0
+        // itself. See `cross_table_lookup::eval_ctl_first_row`.
+        let local_fingerprint = local_values[PC] + local_values[OPCODE_VALUE] * alpha;
+        yield_constr.constraint_first_row(crate::cross_table_lookup::eval_ctl_first_row(
+            local_values[CTL_Z],
+            local_fingerprint,
+            P::ONES * beta,
+            local_values[IS_EXECUTED],
+        ));
+
+        // A second, separate CTL running-sum transition tying this
+        // table's `Lb`/`Sb` rows to `MemoryStark`'s access trace. The
+        // fingerprint folds in `STEP_TIMESTAMP` rather than relying on
+        // trace order, since the memory table is sorted by address
+        // first. `MEM_CTL_Z` is an inclusive prefix sum, so the term
+        // folded in between `local` and `next` is `next`'s own
+        // fingerprint/multiplicity, not `local`'s.
+        let mem_beta = public_inputs[MEM_CTL_BETA];
+        let mem_alpha = public_inputs[MEM_CTL_ALPHA];
+        let next_opcode = |op: u8| next_values[opcode_col(op)];
+        let next_mem_multiplicity = next_opcode(8) + next_opcode(9);
+        let next_mem_fingerprint = next_values[MEM_ADDR]
+            + next_values[MEM_VALUE] * mem_alpha
+            + next_values[STEP_TIMESTAMP] * mem_alpha * mem_alpha;
+        yield_constr.constraint_transition(crate::cross_table_lookup::eval_ctl_transition(
+            local_values[MEM_CTL_Z],
+            next_values[MEM_CTL_Z],
+            next_mem_fingerprint,
+            P::ONES * mem_beta,
+            next_mem_multiplicity,
+        ));
+
+        // First-row pin for the memory CTL, mirroring the program-CTL
+        // one above.
+        let mem_multiplicity = local_values[opcode_col(8)] + local_values[opcode_col(9)];
+        let local_mem_fingerprint = local_values[MEM_ADDR]
+            + local_values[MEM_VALUE] * mem_alpha
+            + local_values[STEP_TIMESTAMP] * mem_alpha * mem_alpha;
+        yield_constr.constraint_first_row(crate::cross_table_lookup::eval_ctl_first_row(
+            local_values[MEM_CTL_Z],
+            local_mem_fingerprint,
+            P::ONES * mem_beta,
+            mem_multiplicity,
+        ));
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values = vars.get_local_values();
+        let next_values = vars.get_next_values();
+        let public_inputs = vars.get_public_inputs();
+        let one = builder.one_extension();
+        let two_fifty_six =
+            builder.constant_extension(<F as Extendable<D>>::Extension::from_canonical_u16(256));
+
+        let is_executed = local_values[IS_EXECUTED];
+        let c = ext_bool_check(builder, is_executed);
+        yield_constr.constraint(builder, c);
+
+        let opcode = |i: u8| local_values[opcode_col(i)];
+        let mut one_hot_sum = builder.zero_extension();
+        for i in 0..OPCODE_WIDTH {
+            one_hot_sum = builder.add_extension(one_hot_sum, local_values[OPCODE_START + i]);
+        }
+        let one_hot_minus_one = builder.sub_extension(one_hot_sum, one);
+        let c = builder.mul_extension(is_executed, one_hot_minus_one);
+        yield_constr.constraint(builder, c);
+
+        let pc = local_values[PC];
+        let next_pc = local_values[NEXT_PC];
+        let dst_sel = local_values[DST_SEL];
+        let src_sel = local_values[SRC_SEL];
+
+        let b = ext_bool_check(builder, dst_sel);
+        let c = builder.mul_extension(is_executed, b);
+        yield_constr.constraint(builder, c);
+        let b = ext_bool_check(builder, src_sel);
+        let c = builder.mul_extension(is_executed, b);
+        yield_constr.constraint(builder, c);
+
+        let reg_before_0 = local_values[REG_BEFORE_START];
+        let reg_before_1 = local_values[REG_BEFORE_START + 1];
+        let reg_after_0 = local_values[REG_AFTER_START];
+        let reg_after_1 = local_values[REG_AFTER_START + 1];
+
+        let dst_before = ext_select(builder, dst_sel, reg_before_0, reg_before_1);
+        let src_before = ext_select(builder, src_sel, reg_before_0, reg_before_1);
+        let dst_after = ext_select(builder, dst_sel, reg_after_0, reg_after_1);
+        let other_before = ext_select(builder, dst_sel, reg_before_1, reg_before_0);
+        let other_after = ext_select(builder, dst_sel, reg_after_1, reg_after_0);
+
+        let mut touches_registers = opcode(0);
+        for i in [1u8, 2, 3, 4, 5, 8] {
+            touches_registers = builder.add_extension(touches_registers, opcode(i));
+        }
+        let other_diff = builder.sub_extension(other_after, other_before);
+        let c = ext_mul3(builder, is_executed, touches_registers, other_diff);
+        yield_constr.constraint(builder, c);
+
+        // Add: dst_after + carry * 256 == dst_before + src_before
+        let carry = local_values[ADD_CARRY];
+        let b = ext_bool_check(builder, carry);
+        let c = builder.mul_extension(is_executed, b);
+        yield_constr.constraint(builder, c);
+        let carry_term = builder.mul_extension(carry, two_fifty_six);
+        let lhs = builder.add_extension(dst_after, carry_term);
+        let rhs = builder.add_extension(dst_before, src_before);
+        let add_diff = builder.sub_extension(lhs, rhs);
+        let c = ext_mul3(builder, is_executed, opcode(0), add_diff);
+        yield_constr.constraint(builder, c);
+
+        // Sub: dst_before - src_before + borrow * 256 == dst_after
+        let borrow = local_values[SUB_BORROW];
+        let b = ext_bool_check(builder, borrow);
+        let c = builder.mul_extension(is_executed, b);
+        yield_constr.constraint(builder, c);
+        let borrow_term = builder.mul_extension(borrow, two_fifty_six);
+        let lhs = builder.sub_extension(dst_before, src_before);
+        let lhs = builder.add_extension(lhs, borrow_term);
+        let sub_diff = builder.sub_extension(lhs, dst_after);
+        let c = ext_mul3(builder, is_executed, opcode(1), sub_diff);
+        yield_constr.constraint(builder, c);
+
+        // Mul: dst_before * src_before == dst_after + quotient * 256
+        let quotient = local_values[MUL_QUOTIENT];
+        let product = builder.mul_extension(dst_before, src_before);
+        let quotient_term = builder.mul_extension(quotient, two_fifty_six);
+        let rhs = builder.add_extension(dst_after, quotient_term);
+        let mul_diff = builder.sub_extension(product, rhs);
+        let c = ext_mul3(builder, is_executed, opcode(2), mul_diff);
+        yield_constr.constraint(builder, c);
+
+        // Div: dst_after == 0 when src_before == 0 (the VM's defined
+        // behavior for division by zero), else dst_before ==
+        // dst_after * src_before + remainder. `div_is_zero` is an
+        // is-zero gadget over `src_before`, mirroring the Jz/Jnz one
+        // below.
+        let div_is_zero = local_values[DIV_SRC_IS_ZERO];
+        let div_src_inv = local_values[DIV_SRC_INV];
+        let div_remainder = local_values[DIV_REMAINDER];
+        let div_zero_check = builder.mul_extension(div_is_zero, src_before);
+        let c = ext_mul3(builder, is_executed, opcode(3), div_zero_check);
+        yield_constr.constraint(builder, c);
+        let div_src_times_inv = builder.mul_extension(src_before, div_src_inv);
+        let div_not_zero = builder.sub_extension(one, div_is_zero);
+        let div_zero_gadget = builder.sub_extension(div_not_zero, div_src_times_inv);
+        let c = ext_mul3(builder, is_executed, opcode(3), div_zero_gadget);
+        yield_constr.constraint(builder, c);
+        let div_zero_result = builder.mul_extension(div_is_zero, dst_after);
+        let c = ext_mul3(builder, is_executed, opcode(3), div_zero_result);
+        yield_constr.constraint(builder, c);
+        // `remainder < src_before` is NOT checked anywhere — see the
+        // matching note in `eval_packed_generic`.
+        let div_product = builder.mul_extension(dst_after, src_before);
+        let div_rhs = builder.add_extension(div_product, div_remainder);
+        let div_diff = builder.sub_extension(dst_before, div_rhs);
+        let div_diff = builder.mul_extension(div_not_zero, div_diff);
+        let c = ext_mul3(builder, is_executed, opcode(3), div_diff);
+        yield_constr.constraint(builder, c);
+
+        // Shl/Shr: shift-amount one-hot decomposition.
+        let shift_one_hot: Vec<ExtensionTarget<D>> = (0..SHIFT_AMOUNT_ONE_HOT_WIDTH)
+            .map(|i| local_values[SHIFT_AMOUNT_ONE_HOT_START + i])
+            .collect();
+        let is_shift = builder.add_extension(opcode(4), opcode(5));
+        let mut shift_one_hot_sum = builder.zero_extension();
+        for &x in &shift_one_hot {
+            shift_one_hot_sum = builder.add_extension(shift_one_hot_sum, x);
+        }
+        let shift_one_hot_minus_one = builder.sub_extension(shift_one_hot_sum, one);
+        let c = ext_mul3(builder, is_executed, is_shift, shift_one_hot_minus_one);
+        yield_constr.constraint(builder, c);
+
+        let mut shift_amount_from_one_hot = builder.zero_extension();
+        for (i, &x) in shift_one_hot
+            .iter()
+            .enumerate()
+        {
+            let weight = builder.constant_extension(<F as Extendable<D>>::Extension::from_canonical_u64(i as u64));
+            shift_amount_from_one_hot = builder.mul_add_extension(x, weight, shift_amount_from_one_hot);
+        }
+        // `shift_amount_from_one_hot` only ever encodes `src_before mod
+        // 8`, so checking it directly against `src_before` (rather than
+        // via `SHIFT_SRC_QUOTIENT`) would wrongly reject any `src`
+        // register value of 8 or more.
+        let shift_src_quotient = local_values[SHIFT_SRC_QUOTIENT];
+        let eight =
+            builder.constant_extension(<F as Extendable<D>>::Extension::from_canonical_u16(8));
+        let shift_src_quotient_term = builder.mul_extension(shift_src_quotient, eight);
+        let shift_src_rhs =
+            builder.add_extension(shift_amount_from_one_hot, shift_src_quotient_term);
+        let shift_src_diff = builder.sub_extension(src_before, shift_src_rhs);
+        let c = ext_mul3(builder, is_executed, is_shift, shift_src_diff);
+        yield_constr.constraint(builder, c);
+
+        let multiplier = local_values[SHIFT_MULTIPLIER];
+        let mut multiplier_from_one_hot = builder.zero_extension();
+        for (i, &x) in shift_one_hot
+            .iter()
+            .enumerate()
+        {
+            let weight = builder.constant_extension(<F as Extendable<D>>::Extension::from_canonical_u64(1u64 << i));
+            multiplier_from_one_hot = builder.mul_add_extension(x, weight, multiplier_from_one_hot);
+        }
+        let multiplier_diff = builder.sub_extension(multiplier, multiplier_from_one_hot);
+        let c = ext_mul3(builder, is_executed, is_shift, multiplier_diff);
+        yield_constr.constraint(builder, c);
+
+        // Shl: dst_before * multiplier == dst_after + shift_quotient * 256.
+        let shift_quotient = local_values[SHIFT_QUOTIENT];
+        let product = builder.mul_extension(dst_before, multiplier);
+        let quotient_term = builder.mul_extension(shift_quotient, two_fifty_six);
+        let rhs = builder.add_extension(dst_after, quotient_term);
+        let shl_diff = builder.sub_extension(product, rhs);
+        let c = ext_mul3(builder, is_executed, opcode(4), shl_diff);
+        yield_constr.constraint(builder, c);
+
+        // Shr: dst_before == dst_after * multiplier + shift_remainder.
+        let shift_remainder = local_values[SHIFT_REMAINDER];
+        let product = builder.mul_extension(dst_after, multiplier);
+        let rhs = builder.add_extension(product, shift_remainder);
+        let shr_diff = builder.sub_extension(dst_before, rhs);
+        let c = ext_mul3(builder, is_executed, opcode(5), shr_diff);
+        yield_constr.constraint(builder, c);
+
+        // Jz/Jnz: the tested-register zero gadget.
+        let tested = local_values[TESTED_REG];
+        let tested_inv = local_values[TESTED_REG_INV];
+        let tested_is_zero = local_values[TESTED_REG_IS_ZERO];
+        let is_jump = builder.add_extension(opcode(6), opcode(7));
+
+        let zero_check = builder.mul_extension(tested_is_zero, tested);
+        let c = ext_mul3(builder, is_executed, is_jump, zero_check);
+        yield_constr.constraint(builder, c);
+
+        let tested_times_inv = builder.mul_extension(tested, tested_inv);
+        let lhs = builder.sub_extension(one, tested_is_zero);
+        let zero_gadget = builder.sub_extension(lhs, tested_times_inv);
+        let c = ext_mul3(builder, is_executed, is_jump, zero_gadget);
+        yield_constr.constraint(builder, c);
+
+        let target = local_values[JUMP_TARGET];
+        let fallthrough = builder.add_extension(pc, one);
+
+        // Jz: next_pc == target when tested == 0, else next_pc == pc + 1.
+        let not_zero = builder.sub_extension(one, tested_is_zero);
+        let jz_target = builder.mul_extension(tested_is_zero, target);
+        let jz_target = builder.mul_add_extension(not_zero, fallthrough, jz_target);
+        let jz_diff = builder.sub_extension(next_pc, jz_target);
+        let c = ext_mul3(builder, is_executed, opcode(6), jz_diff);
+        yield_constr.constraint(builder, c);
+
+        // Jnz: next_pc == target when tested != 0, else next_pc == pc + 1.
+        let jnz_target = builder.mul_extension(not_zero, target);
+        let jnz_target = builder.mul_add_extension(tested_is_zero, fallthrough, jnz_target);
+        let jnz_diff = builder.sub_extension(next_pc, jnz_target);
+        let c = ext_mul3(builder, is_executed, opcode(7), jnz_diff);
+        yield_constr.constraint(builder, c);
+
+        // Halt freezes pc and the whole register file.
+        let next_pc_diff = builder.sub_extension(next_pc, pc);
+        let c = ext_mul3(builder, is_executed, opcode(10), next_pc_diff);
+        yield_constr.constraint(builder, c);
+        let reg0_diff = builder.sub_extension(reg_after_0, reg_before_0);
+        let c = ext_mul3(builder, is_executed, opcode(10), reg0_diff);
+        yield_constr.constraint(builder, c);
+        let reg1_diff = builder.sub_extension(reg_after_1, reg_before_1);
+        let c = ext_mul3(builder, is_executed, opcode(10), reg1_diff);
+        yield_constr.constraint(builder, c);
+
+        // Every other (non-jump, non-halt) instruction simply advances
+        // the program counter by one.
+        let falls_through = builder.sub_extension(one, is_jump);
+        let falls_through = builder.sub_extension(falls_through, opcode(10));
+        let fallthrough_diff = builder.sub_extension(next_pc, fallthrough);
+        let c = ext_mul3(builder, is_executed, falls_through, fallthrough_diff);
+        yield_constr.constraint(builder, c);
+
+        // Lb: dst_after == mem_value.
+        let mem_value = local_values[MEM_VALUE];
+        let lb_diff = builder.sub_extension(dst_after, mem_value);
+        let c = ext_mul3(builder, is_executed, opcode(8), lb_diff);
+        yield_constr.constraint(builder, c);
+        // Sb: mem_value == src_before.
+        let sb_diff = builder.sub_extension(mem_value, src_before);
+        let c = ext_mul3(builder, is_executed, opcode(9), sb_diff);
+        yield_constr.constraint(builder, c);
+
+        // Inter-row continuity, mirroring `eval_packed_generic`: the
+        // next executed row's `pc` and starting register file must
+        // match this row's `NEXT_PC` and ending register file.
+        let next_is_executed = next_values[IS_EXECUTED];
+        let next_row_pc_diff = builder.sub_extension(next_values[PC], next_pc);
+        let c = builder.mul_extension(next_is_executed, next_row_pc_diff);
+        yield_constr.constraint_transition(builder, c);
+        let next_reg0_diff = builder.sub_extension(next_values[REG_BEFORE_START], reg_after_0);
+        let c = builder.mul_extension(next_is_executed, next_reg0_diff);
+        yield_constr.constraint_transition(builder, c);
+        let next_reg1_diff =
+            builder.sub_extension(next_values[REG_BEFORE_START + 1], reg_after_1);
+        let c = builder.mul_extension(next_is_executed, next_reg1_diff);
+        yield_constr.constraint_transition(builder, c);
+
+        // CTL running-sum transition, mirroring `eval_packed_generic`:
+        // the term folded in between `local` and `next` is `next`'s own
+        // fingerprint/`IS_EXECUTED` (see `cross_table_lookup`).
+        let beta = public_inputs[PROGRAM_CTL_BETA];
+        let alpha = public_inputs[PROGRAM_CTL_ALPHA];
+        let next_pc_value = next_values[PC];
+        let next_fingerprint =
+            builder.mul_add_extension(next_values[OPCODE_VALUE], alpha, next_pc_value);
+        let z_diff = builder.sub_extension(next_values[CTL_Z], local_values[CTL_Z]);
+        let beta_minus_fingerprint = builder.sub_extension(beta, next_fingerprint);
+        let running_sum_term = builder.mul_extension(z_diff, beta_minus_fingerprint);
+        let ctl_constraint =
+            builder.sub_extension(running_sum_term, next_values[IS_EXECUTED]);
+        yield_constr.constraint_transition(builder, ctl_constraint);
+
+        // First-row pin, mirroring `eval_packed_generic`.
+        let local_fingerprint =
+            builder.mul_add_extension(local_values[OPCODE_VALUE], alpha, local_values[PC]);
+        let local_beta_minus_fingerprint = builder.sub_extension(beta, local_fingerprint);
+        let local_running_sum_term =
+            builder.mul_extension(local_values[CTL_Z], local_beta_minus_fingerprint);
+        let first_row_constraint =
+            builder.sub_extension(local_running_sum_term, local_values[IS_EXECUTED]);
+        yield_constr.constraint_first_row(builder, first_row_constraint);
+
+        // Second CTL running-sum transition, into `MemoryStark`,
+        // mirroring `eval_packed_generic`: the term folded in between
+        // `local` and `next` is `next`'s own fingerprint/multiplicity.
+        let next_opcode = |i: u8| next_values[opcode_col(i)];
+        let mem_beta = public_inputs[MEM_CTL_BETA];
+        let mem_alpha = public_inputs[MEM_CTL_ALPHA];
+        let next_mem_multiplicity = builder.add_extension(next_opcode(8), next_opcode(9));
+        let mem_alpha_sq = builder.mul_extension(mem_alpha, mem_alpha);
+        let next_mem_fingerprint =
+            builder.mul_add_extension(next_values[MEM_VALUE], mem_alpha, next_values[MEM_ADDR]);
+        let next_mem_fingerprint = builder.mul_add_extension(
+            next_values[STEP_TIMESTAMP],
+            mem_alpha_sq,
+            next_mem_fingerprint,
+        );
+        let mem_z_diff = builder.sub_extension(next_values[MEM_CTL_Z], local_values[MEM_CTL_Z]);
+        let mem_beta_minus_fingerprint = builder.sub_extension(mem_beta, next_mem_fingerprint);
+        let mem_running_sum_term =
+            builder.mul_extension(mem_z_diff, mem_beta_minus_fingerprint);
+        let mem_ctl_constraint =
+            builder.sub_extension(mem_running_sum_term, next_mem_multiplicity);
+        yield_constr.constraint_transition(builder, mem_ctl_constraint);
+
+        // First-row pin for the memory CTL, mirroring the program-CTL
+        // one above.
+        let local_mem_multiplicity = builder.add_extension(opcode(8), opcode(9));
+        let local_mem_fingerprint =
+            builder.mul_add_extension(local_values[MEM_VALUE], mem_alpha, local_values[MEM_ADDR]);
+        let local_mem_fingerprint = builder.mul_add_extension(
+            local_values[STEP_TIMESTAMP],
+            mem_alpha_sq,
+            local_mem_fingerprint,
+        );
+        let local_mem_beta_minus_fingerprint =
+            builder.sub_extension(mem_beta, local_mem_fingerprint);
+        let local_mem_running_sum_term = builder.mul_extension(
+            local_values[MEM_CTL_Z],
+            local_mem_beta_minus_fingerprint,
+        );
+        let mem_first_row_constraint =
+            builder.sub_extension(local_mem_running_sum_term, local_mem_multiplicity);
+        yield_constr.constraint_first_row(builder, mem_first_row_constraint);
+    }
+
+    // The `select(sel, a, b) = a + sel * (b - a)` register-selection
+    // gadget is itself degree 2 (`sel` times a linear combination of
+    // columns), so `dst_before`/`dst_after`/`src_before` are each
+    // degree 2. The Div remainder constraint multiplies one such
+    // selected value by another (`dst_after * src_before`, degree 4)
+    // and gates the whole thing behind three more degree-1 factors
+    // (`is_executed`, `is_div`, `1 - div_is_zero`), for a total degree
+    // of 7 — the highest of any constraint in this file (Mul tops out
+    // at 6, everything else lower). `3` was stale from before the
+    // register-selector gadget existed.
+    fn constraint_degree(&self) -> usize {
+        7
+    }
+}
+
+#[cfg(test)]
+mod tests {
+
+    use std::collections::HashMap;
+
+    use plonky2::{
+        field::goldilocks_field::GoldilocksField,
+        plonk::config::{
+            GenericConfig,
+            PoseidonGoldilocksConfig,
+        },
+        util::timing::TimingTree,
+    };
+    use starky::{
+        config::StarkConfig,
+        proof::StarkProofWithPublicInputs,
+        prover::prove,
+        verifier::verify_stark_proof,
+    };
+
+    use plonky2::field::types::Sample;
+
+    use super::*;
+    use crate::{
+        cross_table_lookup::CtlChallenge,
+        vm_specs::{
+            InstructionLocation,
+            MemoryLocation,
+            Register,
+        },
+    };
+
+    fn prove_and_verify(program: &Program) {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = CpuStark<F, D>;
+        type PR = StarkProofWithPublicInputs<GoldilocksField, C, 2>;
+
+        let stark = S::new();
+        let mut config = StarkConfig::standard_fast_config();
+        // Need to do this since our table is small. Need atleast 1<<5
+        // sized table to not affect this
+        config
+            .fri_config
+            .cap_height = 1;
+        let ctl_challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+        let mem_ctl_challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+        let trace =
+            CpuStark::<F, D>::generate_trace(program, ctl_challenge, mem_ctl_challenge);
+        let public_inputs = [
+            ctl_challenge.beta,
+            ctl_challenge.alpha,
+            mem_ctl_challenge.beta,
+            mem_ctl_challenge.alpha,
+        ];
+        let proof: Result<PR, anyhow::Error> = prove(
+            stark.clone(),
+            &config,
+            trace,
+            &public_inputs,
+            &mut TimingTree::default(),
+        );
+        assert!(proof.is_ok());
+        let verification = verify_stark_proof(stark, proof.unwrap(), &config);
+        assert!(verification.is_ok());
+    }
+
+    #[test]
+    fn test_halt_only_program() {
+        let program = Program {
+            entry_point: 0,
+            code: HashMap::from([(0, Instruction::Halt)]),
+            memory_init: HashMap::new(),
+        };
+        prove_and_verify(&program);
+    }
+
+    #[test]
+    fn test_add_sub_jump_program() {
+        // R0 = R0 + R1; R0 = R0 - R1; if R0 != 0 jump to 3 else halt.
+        let program = Program {
+            entry_point: 0,
+            code: HashMap::from([
+                (0, Instruction::Add(Register::R0, Register::R1)),
+                (1, Instruction::Sub(Register::R0, Register::R1)),
+                (2, Instruction::Jnz(Register::R0, InstructionLocation(3))),
+                (3, Instruction::Halt),
+            ]),
+            memory_init: HashMap::new(),
+        };
+        prove_and_verify(&program);
+    }
+
+    #[test]
+    fn test_nonzero_arithmetic_program() {
+        // Loads nonzero operands from `memory_init` via Lb, then drives
+        // every arithmetic instruction through its overflow/remainder
+        // gadget with nonzero values: R0 = 201 (odd), R1 = 7; R0 *= R1
+        // overflows (1407 = 5*256 + 127, nonzero quotient); R0 >>= 1
+        // (odd dst, nonzero SHIFT_REMAINDER); R0 <<= 4 overflows again
+        // (1008 = 3*256 + 240, nonzero quotient); R0 /= 7 (240 = 34*7 +
+        // 2, nonzero DIV_REMAINDER).
+        let program = Program {
+            entry_point: 0,
+            code: HashMap::from([
+                (0, Instruction::Lb(Register::R0, MemoryLocation(0))),
+                (1, Instruction::Lb(Register::R1, MemoryLocation(1))),
+                (2, Instruction::Mul(Register::R0, Register::R1)),
+                (3, Instruction::Lb(Register::R1, MemoryLocation(2))),
+                (4, Instruction::Shr(Register::R0, Register::R1)),
+                (5, Instruction::Lb(Register::R1, MemoryLocation(3))),
+                (6, Instruction::Shl(Register::R0, Register::R1)),
+                (7, Instruction::Lb(Register::R1, MemoryLocation(4))),
+                (8, Instruction::Div(Register::R0, Register::R1)),
+                (9, Instruction::Halt),
+            ]),
+            memory_init: HashMap::from([(0, 201), (1, 7), (2, 1), (3, 4), (4, 7)]),
+        };
+        prove_and_verify(&program);
+    }
+
+    #[test]
+    fn test_tampered_mul_result_fails_to_prove() {
+        let program = Program {
+            entry_point: 0,
+            code: HashMap::from([
+                (0, Instruction::Lb(Register::R0, MemoryLocation(0))),
+                (1, Instruction::Lb(Register::R1, MemoryLocation(1))),
+                (2, Instruction::Mul(Register::R0, Register::R1)),
+                (3, Instruction::Halt),
+            ]),
+            memory_init: HashMap::from([(0, 201), (1, 7)]),
+        };
+
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type PR = StarkProofWithPublicInputs<GoldilocksField, C, 2>;
+
+        let stark = CpuStark::<F, D>::new();
+        let mut config = StarkConfig::standard_fast_config();
+        config
+            .fri_config
+            .cap_height = 1;
+        let ctl_challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+        let mem_ctl_challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+        let mut trace =
+            CpuStark::<F, D>::generate_trace(&program, ctl_challenge, mem_ctl_challenge);
+        // Row 2 is the `Mul` step; bump its result by one so
+        // `dst_before * src_before == dst_after + quotient * 256` no
+        // longer holds.
+        trace[REG_AFTER_START].values[2] += F::ONE;
+
+        let public_inputs = [
+            ctl_challenge.beta,
+            ctl_challenge.alpha,
+            mem_ctl_challenge.beta,
+            mem_ctl_challenge.alpha,
+        ];
+        let proof: Result<PR, anyhow::Error> = prove(
+            stark.clone(),
+            &config,
+            trace,
+            &public_inputs,
+            &mut TimingTree::default(),
+        );
+        assert!(proof.is_err());
+    }
+}
@@ -0,0 +1,328 @@
+//! A generic logUp lookup argument STARK over a `LookupTable` subtable
+//! (see `lookup_table`). This table's trace *is* the subtable: one row
+//! per `(input_a, input_b, output)` entry in its domain, plus a
+//! multiplicity column counting how many times an instruction's
+//! decomposed queries referenced that row. It's structurally the same
+//! cross-table lookup as `ProgramInstructionsStark`'s tie to
+//! `CpuStark` (a "looked" table whose rows carry a reference count),
+//! just keyed on chunk-pair queries instead of `(pc, opcode)`; the
+//! "looking" side (an instruction's `CpuStark` row decomposed via
+//! `LookupTable::decompose` into this subtable's queries) is wired up
+//! separately as new instructions adopt it.
+//!
+//! Because every subtable in `lookup_table` materializes its *entire*
+//! domain (so a lookup can never fail to find a match), its row count
+//! is already a power of two; this STARK only pads a subtable shorter
+//! than `trace_util::MIN_TRACE_LEN`.
+
+use core::marker::PhantomData;
+use plonky2::{
+    field::{
+        extension::{
+            Extendable,
+            FieldExtension,
+        },
+        packed::PackedField,
+        polynomial::PolynomialValues,
+    },
+    hash::hash_types::RichField,
+    iop::ext_target::ExtensionTarget,
+    plonk::circuit_builder::CircuitBuilder,
+};
+use starky::{
+    constraint_consumer::{
+        ConstraintConsumer,
+        RecursiveConstraintConsumer,
+    },
+    evaluation_frame::{
+        StarkEvaluationFrame,
+        StarkFrame,
+    },
+    stark::Stark,
+    util::trace_rows_to_poly_values,
+};
+
+use crate::{
+    cross_table_lookup::CtlChallenge,
+    lookup_table::LookupTable,
+};
+
+// Table description: one row per `(INPUT_A, INPUT_B, OUTPUT)` entry in
+// `T::rows()`, in that order. `MULTIPLICITY` is how many times an
+// instruction's decomposed queries referenced this row, and `CTL_Z` is
+// the logUp running sum tying those queries to it (see
+// `cross_table_lookup`).
+const INPUT_A: usize = 0;
+const INPUT_B: usize = 1;
+const OUTPUT: usize = 2;
+const MULTIPLICITY: usize = OUTPUT + 1;
+const CTL_Z: usize = MULTIPLICITY + 1;
+const NUMBER_OF_COLS: usize = CTL_Z + 1;
+// Public inputs 0/1 carry the CTL challenges `beta`/`alpha`.
+const CTL_BETA: usize = 0;
+const CTL_ALPHA: usize = 1;
+const PUBLIC_INPUTS: usize = 2;
+
+#[derive(Clone, Copy)]
+pub struct LookupStark<F, const D: usize, T> {
+    pub _f: PhantomData<F>,
+    _t: PhantomData<T>,
+}
+
+impl<F, const D: usize, T> LookupStark<F, D, T>
+where
+    F: RichField + Extendable<D>,
+    T: LookupTable,
+{
+    pub fn new() -> Self {
+        Self {
+            _f: PhantomData,
+            _t: PhantomData,
+        }
+    }
+
+    /// `queries` is every `(input_a, input_b)` chunk pair an
+    /// instruction's decomposed operation referenced this subtable
+    /// with, across the whole execution trace.
+    pub fn generate_trace(
+        queries: &[(u8, u8)],
+        ctl_challenge: CtlChallenge<F>,
+    ) -> Vec<PolynomialValues<F>>
+    where
+        F: RichField,
+    {
+        let table_rows = T::rows();
+        let mut multiplicities = vec![0u64; table_rows.len()];
+        for &(a, b) in queries {
+            let index = table_rows
+                .iter()
+                .position(|&(row_a, row_b, _)| row_a == a && row_b == b)
+                .expect("lookup query outside the subtable's domain");
+            multiplicities[index] += 1;
+        }
+
+        let mut trace = table_rows
+            .iter()
+            .zip(multiplicities.iter())
+            .map(|(&(a, b, output), &multiplicity)| {
+                let mut row = [F::ZERO; NUMBER_OF_COLS];
+                row[INPUT_A] = F::from_canonical_u8(a);
+                row[INPUT_B] = F::from_canonical_u8(b);
+                row[OUTPUT] = F::from_canonical_u8(output);
+                row[MULTIPLICITY] = F::from_canonical_u64(multiplicity);
+                row
+            })
+            .collect::<Vec<[F; NUMBER_OF_COLS]>>();
+
+        let ctl_z = crate::cross_table_lookup::ctl_running_sum_from_fingerprints(
+            &trace
+                .iter()
+                .map(|row| {
+                    crate::cross_table_lookup::fingerprint_n(
+                        &[row[INPUT_A], row[INPUT_B], row[OUTPUT]],
+                        &ctl_challenge,
+                    )
+                })
+                .collect::<Vec<_>>(),
+            &trace
+                .iter()
+                .map(|row| row[MULTIPLICITY])
+                .collect::<Vec<_>>(),
+            ctl_challenge.beta,
+        );
+        for (row, &z) in trace
+            .iter_mut()
+            .zip(ctl_z.iter())
+        {
+            row[CTL_Z] = z;
+        }
+
+        // `T::rows()` already materializes its entire domain as a power
+        // of two, but a small subtable (e.g. a future 2-bit lookup)
+        // could still fall short of the prover's minimum FRI size, so
+        // this pads the same way every other table does. Padding rows
+        // are all-zero except `CTL_Z`, which holds at its last real
+        // value (zero multiplicity keeps the running sum unchanged).
+        let last_z = trace
+            .last()
+            .map(|row| row[CTL_Z])
+            .unwrap_or(F::ZERO);
+        let pow2_len = crate::trace_util::padded_trace_len(trace.len());
+        let mut pad_row = [F::ZERO; NUMBER_OF_COLS];
+        pad_row[CTL_Z] = last_z;
+        trace.resize(pow2_len, pad_row);
+
+        trace_rows_to_poly_values(trace)
+    }
+}
+
+impl<F, const D: usize, T> Stark<F, D> for LookupStark<F, D, T>
+where
+    F: RichField + Extendable<D>,
+    T: LookupTable,
+{
+    type EvaluationFrame<FE, P, const D2: usize> = StarkFrame<P, P::Scalar, NUMBER_OF_COLS, PUBLIC_INPUTS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+    type EvaluationFrameTarget = StarkFrame<
+        ExtensionTarget<D>,
+        ExtensionTarget<D>,
+        NUMBER_OF_COLS,
+        PUBLIC_INPUTS,
+    >;
+
+    const COLUMNS: usize = NUMBER_OF_COLS;
+    const PUBLIC_INPUTS: usize = PUBLIC_INPUTS;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values = vars.get_local_values();
+        let next_values = vars.get_next_values();
+        let public_inputs = vars.get_public_inputs();
+
+        // CTL running-sum transition: see `cross_table_lookup`. `CTL_Z`
+        // is an inclusive prefix sum, so the term folded in between
+        // `local` and `next` is `next`'s own fingerprint/multiplicity,
+        // not `local`'s.
+        let beta = public_inputs[CTL_BETA];
+        let alpha = public_inputs[CTL_ALPHA];
+        let next_fingerprint = next_values[INPUT_A]
+            + next_values[INPUT_B] * alpha
+            + next_values[OUTPUT] * alpha * alpha;
+        yield_constr.constraint_transition(crate::cross_table_lookup::eval_ctl_transition(
+            local_values[CTL_Z],
+            next_values[CTL_Z],
+            next_fingerprint,
+            P::ONES * beta,
+            next_values[MULTIPLICITY],
+        ));
+
+        // First-row pin: the transition constraint above only fixes
+        // `CTL_Z` relative to its own previous row, never to row 0
+        // itself. See `cross_table_lookup::eval_ctl_first_row`.
+        let local_fingerprint = local_values[INPUT_A]
+            + local_values[INPUT_B] * alpha
+            + local_values[OUTPUT] * alpha * alpha;
+        yield_constr.constraint_first_row(crate::cross_table_lookup::eval_ctl_first_row(
+            local_values[CTL_Z],
+            local_fingerprint,
+            P::ONES * beta,
+            local_values[MULTIPLICITY],
+        ));
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values = vars.get_local_values();
+        let next_values = vars.get_next_values();
+        let public_inputs = vars.get_public_inputs();
+
+        // CTL running-sum transition, mirroring `eval_packed_generic`:
+        // the term folded in between `local` and `next` is `next`'s own
+        // fingerprint/multiplicity (see `cross_table_lookup`).
+        let beta = public_inputs[CTL_BETA];
+        let alpha = public_inputs[CTL_ALPHA];
+        let alpha_sq = builder.mul_extension(alpha, alpha);
+        let next_fingerprint =
+            builder.mul_add_extension(next_values[INPUT_B], alpha, next_values[INPUT_A]);
+        let next_fingerprint =
+            builder.mul_add_extension(next_values[OUTPUT], alpha_sq, next_fingerprint);
+        let z_diff = builder.sub_extension(next_values[CTL_Z], local_values[CTL_Z]);
+        let beta_minus_fingerprint = builder.sub_extension(beta, next_fingerprint);
+        let running_sum_term = builder.mul_extension(z_diff, beta_minus_fingerprint);
+        let ctl_constraint =
+            builder.sub_extension(running_sum_term, next_values[MULTIPLICITY]);
+        yield_constr.constraint_transition(builder, ctl_constraint);
+
+        // First-row pin, mirroring `eval_packed_generic`.
+        let local_fingerprint =
+            builder.mul_add_extension(local_values[INPUT_B], alpha, local_values[INPUT_A]);
+        let local_fingerprint =
+            builder.mul_add_extension(local_values[OUTPUT], alpha_sq, local_fingerprint);
+        let local_beta_minus_fingerprint = builder.sub_extension(beta, local_fingerprint);
+        let local_running_sum_term =
+            builder.mul_extension(local_values[CTL_Z], local_beta_minus_fingerprint);
+        let first_row_constraint =
+            builder.sub_extension(local_running_sum_term, local_values[MULTIPLICITY]);
+        yield_constr.constraint_first_row(builder, first_row_constraint);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        3
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::{
+        field::{
+            goldilocks_field::GoldilocksField,
+            types::Sample,
+        },
+        plonk::config::{
+            GenericConfig,
+            PoseidonGoldilocksConfig,
+        },
+        util::timing::TimingTree,
+    };
+    use starky::{
+        config::StarkConfig,
+        proof::StarkProofWithPublicInputs,
+        prover::prove,
+        verifier::verify_stark_proof,
+    };
+
+    use super::*;
+    use crate::lookup_table::{
+        MulLookupTable,
+        RangeCheckU8LookupTable,
+    };
+
+    fn prove_and_verify<T: LookupTable>(queries: &[(u8, u8)]) {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type PR = StarkProofWithPublicInputs<GoldilocksField, C, 2>;
+
+        let stark = LookupStark::<F, D, T>::new();
+        let config = StarkConfig::standard_fast_config();
+        let ctl_challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+        let trace = LookupStark::<F, D, T>::generate_trace(queries, ctl_challenge);
+        let public_inputs = [ctl_challenge.beta, ctl_challenge.alpha];
+        let proof: Result<PR, anyhow::Error> = prove(
+            stark.clone(),
+            &config,
+            trace,
+            &public_inputs,
+            &mut TimingTree::default(),
+        );
+        assert!(proof.is_ok());
+        let verification = verify_stark_proof(stark, proof.unwrap(), &config);
+        assert!(verification.is_ok());
+    }
+
+    #[test]
+    fn test_mul_subtable_with_a_handful_of_queries() {
+        let queries = MulLookupTable::decompose(7, 9);
+        prove_and_verify::<MulLookupTable>(&queries);
+    }
+
+    #[test]
+    fn test_range_check_subtable_with_no_queries() {
+        prove_and_verify::<RangeCheckU8LookupTable>(&[]);
+    }
+}
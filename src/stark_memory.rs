@@ -0,0 +1,753 @@
+//! Offline memory checking for the `Lb`/`Sb` accesses `CpuStark` makes.
+//! Where the other two tables commit to the program listing and the
+//! execution trace respectively, this table commits to every memory
+//! access `CpuStark` performs, sorted by address and then by timestamp.
+//! Same-address continuity (a read returns whatever the last write to
+//! that address stored) is then a purely local, row-to-row constraint,
+//! and a grand-product argument separately proves this sorted multiset
+//! of accesses is a faithful permutation of the real, unsorted access
+//! sequence: every address's block of rows is bookended by a synthetic
+//! "virtual init" write (`TIMESTAMP == 0`, value taken from
+//! `Program::memory_init`) and a synthetic "virtual final" read
+//! (restating the block's last real access, or the virtual init if the
+//! address was never really accessed, snapshotting the address's ending
+//! value).
+//!
+//! Every real access (`Lb` or `Sb` alike) contributes to *both* sides of
+//! the grand product: it reads the state the block was in just before
+//! it, and writes its own resulting state (unchanged for a load,
+//! updated for a store) re-stamped with its own timestamp. That keeps
+//! the write-set (every block's virtual init plus every real access's
+//! post-state) and the read-set (every real access's pre-state plus
+//! every block's virtual final) the exact same multiset, which is what
+//! lets their running products be compared directly; a scheme that
+//! instead only lets `Sb` write and only lets `Lb` read has mismatched
+//! set sizes the moment an address is read more than once between
+//! writes, and can never balance.
+//!
+//! Timestamps are the same 1-based, global step index `CpuStark` uses,
+//! so `STEP_TIMESTAMP == 0` is reserved there for exactly this table's
+//! virtual init rows.
+
+use core::marker::PhantomData;
+use plonky2::{
+    field::{
+        extension::{
+            Extendable,
+            FieldExtension,
+        },
+        packed::PackedField,
+        polynomial::PolynomialValues,
+    },
+    hash::hash_types::RichField,
+    iop::ext_target::ExtensionTarget,
+    plonk::circuit_builder::CircuitBuilder,
+};
+use starky::{
+    constraint_consumer::{
+        ConstraintConsumer,
+        RecursiveConstraintConsumer,
+    },
+    evaluation_frame::{
+        StarkEvaluationFrame,
+        StarkFrame,
+    },
+    stark::Stark,
+    util::trace_rows_to_poly_values,
+};
+
+use crate::{
+    cross_table_lookup::{
+        CtlChallenge,
+        MemoryCheckChallenge,
+    },
+    vm_specs::{
+        Instruction,
+        Program,
+    },
+};
+
+// Table description: one row per memory access, sorted by `ADDRESS`
+// then `TIMESTAMP`. Every address's block of rows is bookended by a
+// synthetic write at `TIMESTAMP == 0` (seeded from `memory_init`) and a
+// synthetic final read restating the block's last access; `CTL_MULTIPLICITY`
+// is 1 on the real `Lb`/`Sb` rows in between and 0 on those two and on
+// padding.
+//
+// Column layout:
+const ADDRESS: usize = 0;
+const VALUE: usize = 1;
+const TIMESTAMP: usize = 2;
+const IS_WRITE: usize = TIMESTAMP + 1;
+const FILTER: usize = IS_WRITE + 1;
+// Same-address zero gadget, comparing this row's address to the next
+// row's: `SAME_ADDR == 1` iff `next.ADDRESS - ADDRESS == 0`.
+const ADDR_DIFF_INV: usize = FILTER + 1;
+const SAME_ADDR: usize = ADDR_DIFF_INV + 1;
+// Witnesses that `next.TIMESTAMP - TIMESTAMP != 0` within a block,
+// except into the block's closing virtual read, which restates its
+// predecessor's timestamp and is left at 0 there (full
+// strict ordering beyond non-equality relies on the prover having
+// actually sorted the block, the same honest-prover leniency the rest
+// of this scaffold already accepts around unchecked `u8` ranges; a
+// genuine range-checked less-than belongs with the lookup-table work).
+const TIMESTAMP_DIFF_INV: usize = SAME_ADDR + 1;
+// Pinned (see `eval_packed_generic`) to 1 exactly on a block's closing
+// virtual read — the last row of its block (`SAME_ADDR == 0`) that
+// isn't a write — and 0 everywhere else, including padding. Used by the
+// grand product below to tell a virtual final row apart from a real
+// read or a virtual init row without repeating their degree-3
+// derivation inline at every use site.
+const IS_FINAL: usize = TIMESTAMP_DIFF_INV + 1;
+// Running products for the write-set (every row except a block's
+// virtual final folds in its own fingerprint) and read-set (every row
+// except a block's virtual init folds in the fingerprint it reads: its
+// own for a virtual final, its predecessor's for a real access); their
+// final values must agree (see `cross_table_lookup::grand_product_running`).
+const WRITE_PRODUCT: usize = IS_FINAL + 1;
+const READ_PRODUCT: usize = WRITE_PRODUCT + 1;
+const CTL_MULTIPLICITY: usize = READ_PRODUCT + 1;
+// The logUp running sum tying real (non-virtual) rows here back to
+// `CpuStark`'s `Lb`/`Sb` rows (see `cross_table_lookup`).
+const CTL_Z: usize = CTL_MULTIPLICITY + 1;
+
+const NUMBER_OF_COLS: usize = CTL_Z + 1;
+// Public inputs 0/1 carry the memory grand-product challenges (`beta`,
+// `gamma`); 2/3 carry the CTL challenges tying this table back to
+// `CpuStark`.
+const MEMORY_CHECK_BETA: usize = 0;
+const MEMORY_CHECK_GAMMA: usize = 1;
+const CTL_BETA: usize = 2;
+const CTL_ALPHA: usize = 3;
+const PUBLIC_INPUTS: usize = 4;
+
+#[derive(Clone, Copy)]
+pub struct MemoryStark<F, const D: usize> {
+    pub _f: PhantomData<F>,
+}
+
+impl<F, const D: usize> MemoryStark<F, D>
+where
+    F: RichField + Extendable<D>,
+{
+    pub fn new() -> Self {
+        Self { _f: PhantomData }
+    }
+
+    pub fn generate_trace(
+        prog: &Program,
+        memory_check_challenge: MemoryCheckChallenge<F>,
+        ctl_challenge: CtlChallenge<F>,
+    ) -> Vec<PolynomialValues<F>>
+    where
+        F: RichField,
+    {
+        // One (address, value, timestamp, is_write) tuple per real
+        // `Lb`/`Sb` access, using `CpuStark`'s 1-based step index as the
+        // timestamp so the two tables' fingerprints line up.
+        let accesses: Vec<(u8, u8, u64, bool)> = prog
+            .execute()
+            .iter()
+            .enumerate()
+            .filter_map(|(step_index, step)| {
+                let address = step.memory_address?;
+                let value = step.memory_value?;
+                let is_write = matches!(step.instruction, Instruction::Sb(_, _));
+                Some((address, value, step_index as u64 + 1, is_write))
+            })
+            .collect();
+
+        // Bucket accesses by address, seed each bucket with a virtual
+        // init row (timestamp 0, value from `memory_init`), and cap it
+        // with a virtual final row (restating the last real access's
+        // timestamp, snapshotting the address's ending value).
+        let mut by_address: std::collections::BTreeMap<u8, Vec<(u8, u8, u64, bool)>> =
+            std::collections::BTreeMap::new();
+        for &access in &accesses {
+            by_address
+                .entry(access.0)
+                .or_default()
+                .push(access);
+        }
+
+        // (address, value, timestamp, is_write, is_real_access)
+        let mut rows: Vec<(u8, u8, u64, bool, bool)> = Vec::new();
+        for (address, mut block) in by_address {
+            block.sort_by_key(|&(_, _, timestamp, _)| timestamp);
+            let init_value = *prog
+                .memory_init
+                .get(&address)
+                .unwrap_or(&0);
+            let final_value = block
+                .last()
+                .map_or(init_value, |&(_, value, _, _)| value);
+            // Restates the last real access's own timestamp (or the
+            // virtual init's, if the address was never really
+            // accessed) rather than a fresh one past it: the final read
+            // must reuse the exact fingerprint that access's own write
+            // contributed, or the write-set/read-set multisets (see the
+            // module doc comment) don't come out equal.
+            let final_timestamp = block
+                .last()
+                .map_or(0, |&(_, _, timestamp, _)| timestamp);
+
+            rows.push((address, init_value, 0, true, false));
+            rows.extend(
+                block
+                    .into_iter()
+                    .map(|(a, v, t, w)| (a, v, t, w, true)),
+            );
+            rows.push((address, final_value, final_timestamp, false, false));
+        }
+
+        let fingerprint = |address: u8, value: u8, timestamp: u64| {
+            crate::cross_table_lookup::memory_fingerprint(
+                F::from_canonical_u8(address),
+                F::from_canonical_u8(value),
+                F::from_canonical_u64(timestamp),
+                &memory_check_challenge,
+            )
+        };
+
+        let mut write_product = F::ONE;
+        let mut read_product = F::ONE;
+        let mut trace: Vec<[F; NUMBER_OF_COLS]> = Vec::with_capacity(rows.len());
+        // The previous row's own fingerprint, carried forward so a real
+        // access can fold it into the read-set as "the state just
+        // before this access". Harmless to carry across a block
+        // boundary unread: the first row of a new block is always a
+        // virtual init, which doesn't consult it.
+        let mut prev_fingerprint = F::ZERO;
+        for &(address, value, timestamp, is_write, is_real_access) in &rows {
+            let own_fingerprint = fingerprint(address, value, timestamp);
+            let is_final = !is_real_access && !is_write;
+
+            if !is_final {
+                write_product *= own_fingerprint;
+            }
+            if is_real_access {
+                read_product *= prev_fingerprint;
+            } else if is_final {
+                read_product *= own_fingerprint;
+            }
+            prev_fingerprint = own_fingerprint;
+
+            let mut row = [F::ZERO; NUMBER_OF_COLS];
+            row[ADDRESS] = F::from_canonical_u8(address);
+            row[VALUE] = F::from_canonical_u8(value);
+            row[TIMESTAMP] = F::from_canonical_u64(timestamp);
+            row[IS_WRITE] = if is_write { F::ONE } else { F::ZERO };
+            row[FILTER] = F::ONE;
+            row[IS_FINAL] = if is_final { F::ONE } else { F::ZERO };
+            row[WRITE_PRODUCT] = write_product;
+            row[READ_PRODUCT] = read_product;
+            row[CTL_MULTIPLICITY] = if is_real_access { F::ONE } else { F::ZERO };
+            trace.push(row);
+        }
+
+        // Fill the same-address and timestamp-difference gadgets by
+        // looking ahead to the next row; the last real row has no
+        // successor to compare against, so both stay at their default
+        // (the corresponding transition constraints don't apply there
+        // either).
+        for i in 0..trace.len().saturating_sub(1) {
+            let addr_diff = trace[i + 1][ADDRESS] - trace[i][ADDRESS];
+            if addr_diff == F::ZERO {
+                trace[i][SAME_ADDR] = F::ONE;
+                let ts_diff = trace[i + 1][TIMESTAMP] - trace[i][TIMESTAMP];
+                // Zero only into a virtual final row, which restates
+                // the previous row's timestamp.
+                trace[i][TIMESTAMP_DIFF_INV] = ts_diff
+                    .try_inverse()
+                    .unwrap_or(F::ZERO);
+            } else {
+                trace[i][ADDR_DIFF_INV] = addr_diff.inverse();
+            }
+        }
+
+        let ctl_z = crate::cross_table_lookup::ctl_running_sum_from_fingerprints(
+            &trace
+                .iter()
+                .map(|row| {
+                    crate::cross_table_lookup::fingerprint_n(
+                        &[row[ADDRESS], row[VALUE], row[TIMESTAMP]],
+                        &ctl_challenge,
+                    )
+                })
+                .collect::<Vec<_>>(),
+            &trace
+                .iter()
+                .map(|row| row[CTL_MULTIPLICITY])
+                .collect::<Vec<_>>(),
+            ctl_challenge.beta,
+        );
+        for (row, &z) in trace
+            .iter_mut()
+            .zip(ctl_z.iter())
+        {
+            row[CTL_Z] = z;
+        }
+
+        // Pad to a power of two. Padding rows are all-zero (in
+        // particular `FILTER == 0`, which frees the same-address/value/
+        // timestamp transition constraints above, and `IS_FINAL`'s
+        // pinning constraint, from applying at the real/padding
+        // boundary) except for the running products and CTL sum, which
+        // are carried forward unchanged — defaulting to the grand
+        // product's identity value (1) rather than 0 when the table has
+        // no rows at all (no addresses ever touched).
+        let last_write_product = trace
+            .last()
+            .map(|row| row[WRITE_PRODUCT])
+            .unwrap_or(F::ONE);
+        let last_read_product = trace
+            .last()
+            .map(|row| row[READ_PRODUCT])
+            .unwrap_or(F::ONE);
+        let last_ctl_z = trace
+            .last()
+            .map(|row| row[CTL_Z])
+            .unwrap_or(F::ZERO);
+        let pow2_len = crate::trace_util::padded_trace_len(trace.len());
+        let mut pad_row = [F::ZERO; NUMBER_OF_COLS];
+        pad_row[WRITE_PRODUCT] = last_write_product;
+        pad_row[READ_PRODUCT] = last_read_product;
+        pad_row[CTL_Z] = last_ctl_z;
+        trace.resize(pow2_len, pad_row);
+
+        trace_rows_to_poly_values(trace)
+    }
+}
+
+impl<F, const D: usize> Stark<F, D> for MemoryStark<F, D>
+where
+    F: RichField + Extendable<D>,
+{
+    type EvaluationFrame<FE, P, const D2: usize> = StarkFrame<P, P::Scalar, NUMBER_OF_COLS, PUBLIC_INPUTS>
+    where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>;
+    type EvaluationFrameTarget = StarkFrame<
+        ExtensionTarget<D>,
+        ExtensionTarget<D>,
+        NUMBER_OF_COLS,
+        PUBLIC_INPUTS,
+    >;
+
+    const COLUMNS: usize = NUMBER_OF_COLS;
+    const PUBLIC_INPUTS: usize = PUBLIC_INPUTS;
+
+    fn eval_packed_generic<FE, P, const D2: usize>(
+        &self,
+        vars: &Self::EvaluationFrame<FE, P, D2>,
+        yield_constr: &mut ConstraintConsumer<P>,
+    ) where
+        FE: FieldExtension<D2, BaseField = F>,
+        P: PackedField<Scalar = FE>,
+    {
+        let local_values = vars.get_local_values();
+        let next_values = vars.get_next_values();
+        let public_inputs = vars.get_public_inputs();
+
+        let filter = local_values[FILTER];
+        yield_constr.constraint(filter * (P::ONES - filter));
+
+        let is_write = local_values[IS_WRITE];
+        yield_constr.constraint(is_write * (P::ONES - is_write));
+
+        // Same-address zero gadget: `SAME_ADDR == 1` iff
+        // `next.ADDRESS - ADDRESS == 0`. Gated by `next.FILTER` so the
+        // boundary into padding (and padding-to-padding transitions,
+        // where both rows are all-zero and this would otherwise read as
+        // spuriously "same address") is left unconstrained.
+        let addr = local_values[ADDRESS];
+        let next_addr = next_values[ADDRESS];
+        let next_filter = next_values[FILTER];
+        let addr_diff = next_addr - addr;
+        let addr_diff_inv = local_values[ADDR_DIFF_INV];
+        let same_addr = local_values[SAME_ADDR];
+        yield_constr.constraint_transition(next_filter * same_addr * addr_diff);
+        yield_constr.constraint_transition(
+            next_filter * ((P::ONES - same_addr) - addr_diff * addr_diff_inv),
+        );
+
+        // Within the same address's block, a read returns whatever the
+        // previous row last stored.
+        let next_value = next_values[VALUE];
+        let next_is_write = next_values[IS_WRITE];
+        let value_diff = next_value - local_values[VALUE];
+        yield_constr.constraint_transition(
+            next_filter * same_addr * (P::ONES - next_is_write) * value_diff,
+        );
+
+        // Within the same address's block, consecutive timestamps are
+        // distinct, except into the block's closing virtual read (see
+        // `TIMESTAMP_DIFF_INV`'s doc comment above).
+        let timestamp = local_values[TIMESTAMP];
+        let next_timestamp = next_values[TIMESTAMP];
+        let timestamp_diff = next_timestamp - timestamp;
+        let timestamp_diff_inv = local_values[TIMESTAMP_DIFF_INV];
+        yield_constr.constraint_transition(
+            (next_filter - next_values[IS_FINAL])
+                * same_addr
+                * (P::ONES - timestamp_diff * timestamp_diff_inv),
+        );
+
+        // `IS_FINAL` classifies this row for the grand product below: 1
+        // exactly on a block's closing virtual read (the last row of
+        // its block, i.e. `SAME_ADDR == 0`, that isn't a write) and 0
+        // everywhere else, including padding.
+        let is_final = local_values[IS_FINAL];
+        yield_constr
+            .constraint(is_final - filter * (P::ONES - same_addr) * (P::ONES - is_write));
+
+        // Grand-product: `WRITE_PRODUCT`/`READ_PRODUCT` are inclusive
+        // prefix products (each already folds in its own row's
+        // contribution), so the transition from `local` to `next` folds
+        // in `next`'s own contribution, not `local`'s. `next` writes its
+        // own fingerprint unless it's a virtual final row (every real
+        // access re-stamps its resulting state, and the virtual init
+        // seeds the block); `next` reads `local`'s fingerprint if it's a
+        // real access (the state just before it) or its own if it's a
+        // virtual final row (closing the block), and reads nothing if
+        // it's a virtual init row. The very first row has no
+        // predecessor to transition from, so it's pinned directly by a
+        // first-row constraint below instead.
+        let beta = public_inputs[MEMORY_CHECK_BETA];
+        let gamma = public_inputs[MEMORY_CHECK_GAMMA];
+        let local_fingerprint =
+            gamma - (addr + beta * local_values[VALUE] + beta * beta * timestamp);
+        let local_fingerprint_minus_one = local_fingerprint - P::ONES;
+        let next_fingerprint =
+            gamma - (next_addr + beta * next_values[VALUE] + beta * beta * next_timestamp);
+        let next_fingerprint_minus_one = next_fingerprint - P::ONES;
+
+        let write_product = local_values[WRITE_PRODUCT];
+        let read_product = local_values[READ_PRODUCT];
+        let next_write_product = next_values[WRITE_PRODUCT];
+        let next_read_product = next_values[READ_PRODUCT];
+        let next_is_final = next_values[IS_FINAL];
+        let next_ctl_multiplicity = next_values[CTL_MULTIPLICITY];
+
+        let next_write_multiplier =
+            P::ONES + (next_filter - next_is_final) * next_fingerprint_minus_one;
+        let next_read_multiplier = P::ONES
+            + next_ctl_multiplicity * local_fingerprint_minus_one
+            + next_is_final * next_fingerprint_minus_one;
+        yield_constr
+            .constraint_transition(next_write_product - write_product * next_write_multiplier);
+        yield_constr
+            .constraint_transition(next_read_product - read_product * next_read_multiplier);
+
+        // First row: pinned directly via this row's own multiplier
+        // applied to the grand product's identity value (1), since
+        // there is no preceding row for a transition constraint to
+        // tie it to.
+        let ctl_multiplicity = local_values[CTL_MULTIPLICITY];
+        let local_write_multiplier = P::ONES + (filter - is_final) * local_fingerprint_minus_one;
+        let local_read_multiplier =
+            P::ONES + (ctl_multiplicity + is_final) * local_fingerprint_minus_one;
+        yield_constr.constraint_first_row(write_product - local_write_multiplier);
+        yield_constr.constraint_first_row(read_product - local_read_multiplier);
+
+        // Last row: the write-set (+ initial memory) and read-set
+        // (+ final memory) running products must agree — this is the
+        // actual soundness check offline memory checking rests on.
+        yield_constr.constraint_last_row(write_product - read_product);
+
+        // CTL running-sum transition, tying real (non-virtual) rows
+        // here back to `CpuStark`'s `Lb`/`Sb` rows. See
+        // `cross_table_lookup`. `CTL_Z` is an inclusive prefix sum, so
+        // the term folded in between `local` and `next` is `next`'s own
+        // fingerprint/multiplicity, not `local`'s.
+        let ctl_beta = public_inputs[CTL_BETA];
+        let ctl_alpha = public_inputs[CTL_ALPHA];
+        let next_ctl_fingerprint = next_addr
+            + next_values[VALUE] * ctl_alpha
+            + next_values[TIMESTAMP] * ctl_alpha * ctl_alpha;
+        yield_constr.constraint_transition(crate::cross_table_lookup::eval_ctl_transition(
+            local_values[CTL_Z],
+            next_values[CTL_Z],
+            next_ctl_fingerprint,
+            P::ONES * ctl_beta,
+            next_values[CTL_MULTIPLICITY],
+        ));
+
+        // First-row pin: the transition constraint above only fixes
+        // `CTL_Z` relative to its own previous row, never to row 0
+        // itself. See `cross_table_lookup::eval_ctl_first_row`.
+        let local_ctl_fingerprint =
+            addr + local_values[VALUE] * ctl_alpha + local_values[TIMESTAMP] * ctl_alpha * ctl_alpha;
+        yield_constr.constraint_first_row(crate::cross_table_lookup::eval_ctl_first_row(
+            local_values[CTL_Z],
+            local_ctl_fingerprint,
+            P::ONES * ctl_beta,
+            local_values[CTL_MULTIPLICITY],
+        ));
+    }
+
+    fn eval_ext_circuit(
+        &self,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+    ) {
+        let local_values = vars.get_local_values();
+        let next_values = vars.get_next_values();
+        let public_inputs = vars.get_public_inputs();
+        let one = builder.one_extension();
+
+        let filter = local_values[FILTER];
+        let c = builder.mul_sub_extension(filter, filter, filter);
+        yield_constr.constraint(builder, c);
+
+        let is_write = local_values[IS_WRITE];
+        let c = builder.mul_sub_extension(is_write, is_write, is_write);
+        yield_constr.constraint(builder, c);
+
+        let addr = local_values[ADDRESS];
+        let next_addr = next_values[ADDRESS];
+        let next_filter = next_values[FILTER];
+        let addr_diff = builder.sub_extension(next_addr, addr);
+        let addr_diff_inv = local_values[ADDR_DIFF_INV];
+        let same_addr = local_values[SAME_ADDR];
+        let c = builder.mul_extension(same_addr, addr_diff);
+        let c = builder.mul_extension(next_filter, c);
+        yield_constr.constraint_transition(builder, c);
+        let not_same = builder.sub_extension(one, same_addr);
+        let diff_times_inv = builder.mul_extension(addr_diff, addr_diff_inv);
+        let c = builder.sub_extension(not_same, diff_times_inv);
+        let c = builder.mul_extension(next_filter, c);
+        yield_constr.constraint_transition(builder, c);
+
+        let next_value = next_values[VALUE];
+        let next_is_write = next_values[IS_WRITE];
+        let value_diff = builder.sub_extension(next_value, local_values[VALUE]);
+        let not_next_write = builder.sub_extension(one, next_is_write);
+        let c = builder.mul_extension(same_addr, not_next_write);
+        let c = builder.mul_extension(c, value_diff);
+        let c = builder.mul_extension(next_filter, c);
+        yield_constr.constraint_transition(builder, c);
+
+        let timestamp = local_values[TIMESTAMP];
+        let next_timestamp = next_values[TIMESTAMP];
+        let timestamp_diff = builder.sub_extension(next_timestamp, timestamp);
+        let timestamp_diff_inv = local_values[TIMESTAMP_DIFF_INV];
+        let diff_times_inv = builder.mul_extension(timestamp_diff, timestamp_diff_inv);
+        let c = builder.sub_extension(one, diff_times_inv);
+        let c = builder.mul_extension(same_addr, c);
+        let next_not_final = builder.sub_extension(next_filter, next_values[IS_FINAL]);
+        let c = builder.mul_extension(next_not_final, c);
+        yield_constr.constraint_transition(builder, c);
+
+        let beta = public_inputs[MEMORY_CHECK_BETA];
+        let gamma = public_inputs[MEMORY_CHECK_GAMMA];
+        let beta_sq = builder.mul_extension(beta, beta);
+        let local_fingerprint_inner = builder.mul_add_extension(beta, local_values[VALUE], addr);
+        let local_fingerprint_inner =
+            builder.mul_add_extension(beta_sq, timestamp, local_fingerprint_inner);
+        let local_fingerprint = builder.sub_extension(gamma, local_fingerprint_inner);
+        let local_fingerprint_minus_one = builder.sub_extension(local_fingerprint, one);
+        let next_fingerprint_inner =
+            builder.mul_add_extension(beta, next_values[VALUE], next_addr);
+        let next_fingerprint_inner =
+            builder.mul_add_extension(beta_sq, next_timestamp, next_fingerprint_inner);
+        let next_fingerprint = builder.sub_extension(gamma, next_fingerprint_inner);
+        let next_fingerprint_minus_one = builder.sub_extension(next_fingerprint, one);
+
+        // `IS_FINAL` classifies this row for the grand product below:
+        // 1 exactly on a block's closing virtual read (see
+        // `eval_packed_generic`) and 0 everywhere else, including
+        // padding.
+        let is_final = local_values[IS_FINAL];
+        let not_same_addr = builder.sub_extension(one, same_addr);
+        let not_is_write = builder.sub_extension(one, is_write);
+        let is_final_expected = builder.mul_extension(filter, not_same_addr);
+        let is_final_expected = builder.mul_extension(is_final_expected, not_is_write);
+        let c = builder.sub_extension(is_final, is_final_expected);
+        yield_constr.constraint(builder, c);
+
+        let write_product = local_values[WRITE_PRODUCT];
+        let read_product = local_values[READ_PRODUCT];
+        let next_write_product = next_values[WRITE_PRODUCT];
+        let next_read_product = next_values[READ_PRODUCT];
+        let next_is_final = next_values[IS_FINAL];
+        let next_ctl_multiplicity = next_values[CTL_MULTIPLICITY];
+
+        let next_write_gate = builder.sub_extension(next_filter, next_is_final);
+        let next_write_multiplier =
+            builder.mul_add_extension(next_write_gate, next_fingerprint_minus_one, one);
+        let expected_write = builder.mul_extension(write_product, next_write_multiplier);
+        let c = builder.sub_extension(next_write_product, expected_write);
+        yield_constr.constraint_transition(builder, c);
+
+        let next_read_multiplier = builder.mul_add_extension(
+            next_ctl_multiplicity,
+            local_fingerprint_minus_one,
+            one,
+        );
+        let next_read_multiplier = builder.mul_add_extension(
+            next_is_final,
+            next_fingerprint_minus_one,
+            next_read_multiplier,
+        );
+        let expected_read = builder.mul_extension(read_product, next_read_multiplier);
+        let c = builder.sub_extension(next_read_product, expected_read);
+        yield_constr.constraint_transition(builder, c);
+
+        // First row: pinned directly via this row's own multiplier
+        // applied to the grand product's identity value (1).
+        let ctl_multiplicity = local_values[CTL_MULTIPLICITY];
+        let write_gate = builder.sub_extension(filter, is_final);
+        let local_write_multiplier =
+            builder.mul_add_extension(write_gate, local_fingerprint_minus_one, one);
+        let c = builder.sub_extension(write_product, local_write_multiplier);
+        yield_constr.constraint_first_row(builder, c);
+
+        let read_gate = builder.add_extension(ctl_multiplicity, is_final);
+        let local_read_multiplier =
+            builder.mul_add_extension(read_gate, local_fingerprint_minus_one, one);
+        let c = builder.sub_extension(read_product, local_read_multiplier);
+        yield_constr.constraint_first_row(builder, c);
+
+        // Last row: the write-set and read-set running products must
+        // agree (see `eval_packed_generic`).
+        let c = builder.sub_extension(write_product, read_product);
+        yield_constr.constraint_last_row(builder, c);
+
+        // `CTL_Z` is an inclusive prefix sum, so the term folded in
+        // between `local` and `next` is `next`'s own fingerprint/
+        // multiplicity, not `local`'s.
+        let ctl_beta = public_inputs[CTL_BETA];
+        let ctl_alpha = public_inputs[CTL_ALPHA];
+        let ctl_alpha_sq = builder.mul_extension(ctl_alpha, ctl_alpha);
+        let next_ctl_fingerprint =
+            builder.mul_add_extension(next_values[VALUE], ctl_alpha, next_addr);
+        let next_ctl_fingerprint = builder.mul_add_extension(
+            next_values[TIMESTAMP],
+            ctl_alpha_sq,
+            next_ctl_fingerprint,
+        );
+        let z_diff = builder.sub_extension(next_values[CTL_Z], local_values[CTL_Z]);
+        let beta_minus_fingerprint = builder.sub_extension(ctl_beta, next_ctl_fingerprint);
+        let running_sum_term = builder.mul_extension(z_diff, beta_minus_fingerprint);
+        let c = builder.sub_extension(running_sum_term, next_values[CTL_MULTIPLICITY]);
+        yield_constr.constraint_transition(builder, c);
+
+        // First-row pin, mirroring `eval_packed_generic`.
+        let local_ctl_fingerprint = builder.mul_add_extension(local_values[VALUE], ctl_alpha, addr);
+        let local_ctl_fingerprint = builder.mul_add_extension(
+            local_values[TIMESTAMP],
+            ctl_alpha_sq,
+            local_ctl_fingerprint,
+        );
+        let local_beta_minus_fingerprint = builder.sub_extension(ctl_beta, local_ctl_fingerprint);
+        let local_running_sum_term =
+            builder.mul_extension(local_values[CTL_Z], local_beta_minus_fingerprint);
+        let c = builder.sub_extension(local_running_sum_term, local_values[CTL_MULTIPLICITY]);
+        yield_constr.constraint_first_row(builder, c);
+    }
+
+    fn constraint_degree(&self) -> usize {
+        4
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use plonky2::{
+        field::{
+            goldilocks_field::GoldilocksField,
+            types::Sample,
+        },
+        plonk::config::{
+            GenericConfig,
+            PoseidonGoldilocksConfig,
+        },
+        util::timing::TimingTree,
+    };
+    use starky::{
+        config::StarkConfig,
+        proof::StarkProofWithPublicInputs,
+        prover::prove,
+        verifier::verify_stark_proof,
+    };
+
+    use super::*;
+    use crate::vm_specs::{
+        Instruction,
+        MemoryLocation,
+        Register,
+    };
+
+    fn prove_and_verify(program: &Program) {
+        const D: usize = 2;
+        type C = PoseidonGoldilocksConfig;
+        type F = <C as GenericConfig<D>>::F;
+        type S = MemoryStark<F, D>;
+        type PR = StarkProofWithPublicInputs<GoldilocksField, C, 2>;
+
+        let stark = S::new();
+        let mut config = StarkConfig::standard_fast_config();
+        // Need to do this since our table is small. Need atleast 1<<5
+        // sized table to not affect this
+        config
+            .fri_config
+            .cap_height = 1;
+        let memory_check_challenge = MemoryCheckChallenge::<F> {
+            beta: F::rand(),
+            gamma: F::rand(),
+        };
+        let ctl_challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+        let trace =
+            MemoryStark::<F, D>::generate_trace(program, memory_check_challenge, ctl_challenge);
+        let public_inputs = [
+            memory_check_challenge.beta,
+            memory_check_challenge.gamma,
+            ctl_challenge.beta,
+            ctl_challenge.alpha,
+        ];
+        let proof: Result<PR, anyhow::Error> = prove(
+            stark.clone(),
+            &config,
+            trace,
+            &public_inputs,
+            &mut TimingTree::default(),
+        );
+        assert!(proof.is_ok());
+        let verification = verify_stark_proof(stark, proof.unwrap(), &config);
+        assert!(verification.is_ok());
+    }
+
+    #[test]
+    fn test_no_memory_accesses() {
+        let program = Program {
+            entry_point: 0,
+            code: std::collections::HashMap::from([(0, Instruction::Halt)]),
+            memory_init: std::collections::HashMap::new(),
+        };
+        prove_and_verify(&program);
+    }
+
+    #[test]
+    fn test_store_then_load_round_trips() {
+        // R0 <- mem[0] (== 7); mem[5] <- R0; R1 <- mem[5]; halt.
+        let code = std::collections::HashMap::from([
+            (0, Instruction::Lb(Register::R0, MemoryLocation(0))),
+            (1, Instruction::Sb(Register::R0, MemoryLocation(5))),
+            (2, Instruction::Lb(Register::R1, MemoryLocation(5))),
+            (3, Instruction::Halt),
+        ]);
+        let mut memory_init = std::collections::HashMap::new();
+        memory_init.insert(0, 7);
+        let program = Program {
+            entry_point: 0,
+            code,
+            memory_init,
+        };
+        prove_and_verify(&program);
+    }
+}
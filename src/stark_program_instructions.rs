@@ -31,17 +31,34 @@ use starky::{
     util::trace_rows_to_poly_values,
 };
 
-use crate::vm_specs::Program;
+use crate::{
+    cross_table_lookup::CtlChallenge,
+    vm_specs::Program,
+};
 
 // Table description:
-// +-----------------+--------------------+-------------+
-// | Program Counter | Instruction Opcode | Is_Executed |
-// +-----------------+--------------------+-------------+
-// |    ....         |     ....           |    ....     |
-// |    ....         |     ....           |    ....     |
-// +-----------------+--------------------+-------------+
-const NUMBER_OF_COLS: usize = 3;
-const PUBLIC_INPUTS: usize = 0;
+// +-----------------+--------------------+--------+----------------+--------+
+// | Program Counter | Instruction Opcode | Filter | CTL Multiplicity | CTL Z |
+// +-----------------+--------------------+--------+----------------+--------+
+// |    ....         |     ....           |  ....  |      ....      |  ....  |
+// |    ....         |     ....           |  ....  |      ....      |  ....  |
+// +-----------------+--------------------+--------+----------------+--------+
+// `Filter` marks a real (non-padding) row. `CTL Multiplicity` is how
+// many times the CPU execution trace actually visits this `(pc,
+// opcode)` row, and `CTL Z` is the logUp running sum tying the two
+// tables together (see `cross_table_lookup`).
+const PC: usize = 0;
+const OPCODE: usize = 1;
+const FILTER: usize = 2;
+const CTL_MULTIPLICITY: usize = 3;
+const CTL_Z: usize = 4;
+const NUMBER_OF_COLS: usize = CTL_Z + 1;
+// Public inputs 0 and 1 carry the CTL challenges `beta` and `alpha`,
+// drawn via Fiat-Shamir after every participating table's trace has
+// been committed to.
+const PUBLIC_INPUT_BETA: usize = 0;
+const PUBLIC_INPUT_ALPHA: usize = 1;
+const PUBLIC_INPUTS: usize = 2;
 
 #[derive(Clone, Copy)]
 pub struct ProgramInstructionsStark<F, const D: usize> {
@@ -56,30 +73,75 @@ where
         Self { _f: PhantomData }
     }
 
-    pub fn generate_trace(prog: &Program) -> Vec<PolynomialValues<F>>
+    pub fn generate_trace(
+        prog: &Program,
+        ctl_challenge: CtlChallenge<F>,
+    ) -> Vec<PolynomialValues<F>>
     where
         F: RichField,
     {
-        let mut trace = prog
+        // Tally how many times the actual execution trace visits each
+        // `pc`, so every program row carries the multiplicity the CTL
+        // argument needs.
+        let mut visit_counts: std::collections::HashMap<u8, u64> = std::collections::HashMap::new();
+        for step in prog.execute() {
+            *visit_counts
+                .entry(step.pc)
+                .or_insert(0) += 1;
+        }
+
+        let rows = prog
             .code
             .iter()
             .map(|(pc, inst)| {
-                [
-                    // Program Counter (ID = 0)
+                let multiplicity = *visit_counts
+                    .get(pc)
+                    .unwrap_or(&0);
+                (
                     F::from_canonical_u8(*pc),
-                    // Instruction Opcode (ID = 1)
                     F::from_canonical_u8(inst.get_opcode()),
-                    // Filter, true if actual instructions (ID = 2)
-                    F::ONE,
-                ]
+                    F::from_canonical_u64(multiplicity),
+                )
+            })
+            .collect::<Vec<(F, F, F)>>();
+
+        let ctl_z = crate::cross_table_lookup::ctl_running_sum(
+            &rows
+                .iter()
+                .map(|&(pc, opcode, _)| (pc, opcode))
+                .collect::<Vec<_>>(),
+            &rows
+                .iter()
+                .map(|&(_, _, multiplicity)| multiplicity)
+                .collect::<Vec<_>>(),
+            &ctl_challenge,
+        );
+
+        let mut trace = rows
+            .iter()
+            .zip(ctl_z.iter())
+            .map(|(&(pc, opcode, multiplicity), &z)| {
+                let mut row = [F::ZERO; NUMBER_OF_COLS];
+                row[PC] = pc;
+                row[OPCODE] = opcode;
+                row[FILTER] = F::ONE;
+                row[CTL_MULTIPLICITY] = multiplicity;
+                row[CTL_Z] = z;
+                row
             })
             .collect::<Vec<[F; NUMBER_OF_COLS]>>();
 
-        // Need to pad the trace to a len of some power of 2
-        let pow2_len = trace
-            .len()
-            .next_power_of_two();
-        trace.resize(pow2_len, [F::ZERO, F::ZERO, F::ZERO]);
+        // Need to pad the trace to a len of some power of 2. Padding
+        // rows carry zero multiplicity, so the running sum (and hence
+        // `CTL_Z`) simply holds at its last real value.
+        let last_z = trace
+            .last()
+            .map(|row| row[CTL_Z])
+            .unwrap_or(F::ZERO);
+        let pow2_len = crate::trace_util::padded_trace_len(trace.len());
+        let mut pad_row = [F::ZERO; NUMBER_OF_COLS];
+        pad_row[CTL_Z] = last_z;
+        trace.resize(pow2_len, pad_row);
 
         // Convert into polynomial values
         trace_rows_to_poly_values(trace)
@@ -113,19 +175,83 @@ where
         P: PackedField<Scalar = FE>,
     {
         let local_values = vars.get_local_values();
+        let next_values = vars.get_next_values();
+        let public_inputs = vars.get_public_inputs();
 
         // Check if filter column is either 0 or 1
-        let filter_column = local_values[2];
+        let filter_column = local_values[FILTER];
         yield_constr.constraint(filter_column * (P::ONES - filter_column));
+
+        // CTL running-sum transition: see `cross_table_lookup`. `CTL_Z`
+        // is an inclusive prefix sum, so the term folded in between
+        // `local` and `next` is `next`'s own fingerprint/multiplicity,
+        // not `local`'s.
+        let beta = public_inputs[PUBLIC_INPUT_BETA];
+        let alpha = public_inputs[PUBLIC_INPUT_ALPHA];
+        let next_fingerprint = next_values[PC] + next_values[OPCODE] * alpha;
+        yield_constr.constraint_transition(crate::cross_table_lookup::eval_ctl_transition(
+            local_values[CTL_Z],
+            next_values[CTL_Z],
+            next_fingerprint,
+            P::ONES * beta,
+            next_values[CTL_MULTIPLICITY],
+        ));
+
+        // First-row pin: the transition constraint above only fixes
+        // `CTL_Z` relative to its own previous row, never to row 0
+        // itself. See `cross_table_lookup::eval_ctl_first_row`.
+        let local_fingerprint = local_values[PC] + local_values[OPCODE] * alpha;
+        yield_constr.constraint_first_row(crate::cross_table_lookup::eval_ctl_first_row(
+            local_values[CTL_Z],
+            local_fingerprint,
+            P::ONES * beta,
+            local_values[CTL_MULTIPLICITY],
+        ));
     }
 
     fn eval_ext_circuit(
         &self,
-        _builder: &mut CircuitBuilder<F, D>,
-        _vars: &Self::EvaluationFrameTarget,
-        _yield_constr: &mut RecursiveConstraintConsumer<F, D>,
+        builder: &mut CircuitBuilder<F, D>,
+        vars: &Self::EvaluationFrameTarget,
+        yield_constr: &mut RecursiveConstraintConsumer<F, D>,
     ) {
-        unimplemented!()
+        let local_values = vars.get_local_values();
+        let next_values = vars.get_next_values();
+        let public_inputs = vars.get_public_inputs();
+
+        // Check if filter column is either 0 or 1: `filter * (1 -
+        // filter) == filter * filter - filter` up to sign.
+        let filter_column = local_values[FILTER];
+        let filter_constraint =
+            builder.mul_sub_extension(filter_column, filter_column, filter_column);
+        yield_constr.constraint(builder, filter_constraint);
+
+        // CTL running-sum transition, mirroring `eval_packed_generic`:
+        // the term folded in between `local` and `next` is `next`'s own
+        // fingerprint/multiplicity (see `cross_table_lookup`).
+        let beta = public_inputs[PUBLIC_INPUT_BETA];
+        let alpha = public_inputs[PUBLIC_INPUT_ALPHA];
+        let next_fingerprint =
+            builder.mul_add_extension(next_values[OPCODE], alpha, next_values[PC]);
+        let z_diff = builder.sub_extension(next_values[CTL_Z], local_values[CTL_Z]);
+        let beta_minus_fingerprint = builder.sub_extension(beta, next_fingerprint);
+        let running_sum_term = builder.mul_extension(z_diff, beta_minus_fingerprint);
+        let ctl_constraint =
+            builder.sub_extension(running_sum_term, next_values[CTL_MULTIPLICITY]);
+        yield_constr.constraint_transition(builder, ctl_constraint);
+
+        // First-row pin, mirroring `eval_packed_generic`.
+        let local_fingerprint =
+            builder.mul_add_extension(local_values[OPCODE], alpha, local_values[PC]);
+        let local_z_times_beta_minus_fp = {
+            let beta_minus_fingerprint = builder.sub_extension(beta, local_fingerprint);
+            builder.mul_extension(local_values[CTL_Z], beta_minus_fingerprint)
+        };
+        let first_row_constraint = builder.sub_extension(
+            local_z_times_beta_minus_fp,
+            local_values[CTL_MULTIPLICITY],
+        );
+        yield_constr.constraint_first_row(builder, first_row_constraint);
     }
 
     fn constraint_degree(&self) -> usize {
@@ -151,7 +277,10 @@ mod tests {
         verifier::verify_stark_proof,
     };
 
+    use plonky2::field::types::Sample;
+
     use super::*;
+    use crate::cross_table_lookup::CtlChallenge;
 
     #[test]
     fn test_nil_program() {
@@ -169,12 +298,17 @@ mod tests {
             .fri_config
             .cap_height = 1;
         let program = Program::default();
-        let trace = ProgramInstructionsStark::<F, D>::generate_trace(&program);
+        let ctl_challenge = CtlChallenge::<F> {
+            alpha: F::rand(),
+            beta: F::rand(),
+        };
+        let trace = ProgramInstructionsStark::<F, D>::generate_trace(&program, ctl_challenge);
+        let public_inputs = [ctl_challenge.beta, ctl_challenge.alpha];
         let proof: Result<PR, anyhow::Error> = prove(
             stark.clone(),
             &config,
             trace,
-            &[],
+            &public_inputs,
             &mut TimingTree::default(),
         );
         assert!(proof.is_ok());
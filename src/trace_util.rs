@@ -0,0 +1,35 @@
+//! Small shared helpers for building STARK traces, used by every
+//! `generate_trace` in this crate.
+
+/// Every table pads its trace up to at least this length, regardless of
+/// how many real rows it has. A handful of rows (or zero, e.g.
+/// `Program::default()`'s empty `code`) would otherwise round up to a
+/// trace far too small for the configured FRI rate/cap to evaluate
+/// correctly; padding up to at least this size keeps degenerate traces
+/// provable without every caller having to special-case
+/// `StarkConfig::fri_config` by hand.
+pub const MIN_TRACE_LEN: usize = 1 << 5;
+
+/// The power-of-two length a table should pad its trace to: the actual
+/// row count rounded up, saturated to `MIN_TRACE_LEN` so a degenerate
+/// (short, or empty) trace still meets the prover's minimum FRI size.
+pub fn padded_trace_len(real_rows: usize) -> usize {
+    real_rows
+        .next_power_of_two()
+        .max(MIN_TRACE_LEN)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn empty_trace_pads_up_to_the_minimum() {
+        assert_eq!(padded_trace_len(0), MIN_TRACE_LEN);
+    }
+
+    #[test]
+    fn a_trace_already_past_the_minimum_just_rounds_up() {
+        assert_eq!(padded_trace_len(MIN_TRACE_LEN * 2 + 1), MIN_TRACE_LEN * 4);
+    }
+}
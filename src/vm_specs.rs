@@ -92,3 +92,132 @@ pub struct Program {
     /// Initial memory layout at the start of the program
     pub memory_init: HashMap<u8, u8>,
 }
+
+/// One step of the VM's *running* execution, as opposed to the static
+/// listing held in `Program::code`. A program may execute fewer, more
+/// (via looping) or simply a reordered subset of the instructions it
+/// holds, depending on how jumps are taken, so this is recorded
+/// separately from the program listing.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ExecutionStep {
+    /// Program counter this step was fetched from
+    pub pc: u8,
+    /// The instruction that was executed
+    pub instruction: Instruction,
+    /// Register file immediately before the instruction ran
+    pub registers_before: [u8; REGISTER_COUNT],
+    /// Register file immediately after the instruction ran
+    pub registers_after: [u8; REGISTER_COUNT],
+    /// Memory address touched by `Lb`/`Sb`, if any
+    pub memory_address: Option<u8>,
+    /// Value read (for `Lb`) or written (for `Sb`) at `memory_address`
+    pub memory_value: Option<u8>,
+    /// Program counter of the next step
+    pub next_pc: u8,
+}
+
+impl Program {
+    /// Executes the program starting at `entry_point` and returns the
+    /// resulting execution trace, one `ExecutionStep` per instruction
+    /// actually run. Execution stops once a `Halt` is reached or once
+    /// `pc` no longer indexes into `code` (treated as an implicit halt).
+    pub fn execute(&self) -> Vec<ExecutionStep> {
+        let mut registers = [0u8; REGISTER_COUNT];
+        let mut memory = self
+            .memory_init
+            .clone();
+        let mut pc = self.entry_point;
+        let mut steps = Vec::new();
+
+        loop {
+            let Some(instruction) = self
+                .code
+                .get(&pc)
+            else {
+                break;
+            };
+            let registers_before = registers;
+            let mut memory_address = None;
+            let mut memory_value = None;
+            let mut next_pc = pc.wrapping_add(1);
+
+            match instruction {
+                Instruction::Add(dst, src) => {
+                    registers[usize::from(*dst)] = registers[usize::from(*dst)]
+                        .wrapping_add(registers[usize::from(*src)]);
+                }
+                Instruction::Sub(dst, src) => {
+                    registers[usize::from(*dst)] = registers[usize::from(*dst)]
+                        .wrapping_sub(registers[usize::from(*src)]);
+                }
+                Instruction::Mul(dst, src) => {
+                    registers[usize::from(*dst)] = registers[usize::from(*dst)]
+                        .wrapping_mul(registers[usize::from(*src)]);
+                }
+                Instruction::Div(dst, src) => {
+                    let divisor = registers[usize::from(*src)];
+                    registers[usize::from(*dst)] = if divisor == 0 {
+                        0
+                    } else {
+                        registers[usize::from(*dst)] / divisor
+                    };
+                }
+                Instruction::Shl(dst, src) => {
+                    let shift = registers[usize::from(*src)] & 0x7;
+                    registers[usize::from(*dst)] =
+                        registers[usize::from(*dst)].wrapping_shl(shift as u32);
+                }
+                Instruction::Shr(dst, src) => {
+                    let shift = registers[usize::from(*src)] & 0x7;
+                    registers[usize::from(*dst)] =
+                        registers[usize::from(*dst)].wrapping_shr(shift as u32);
+                }
+                Instruction::Jz(reg, loc) => {
+                    if registers[usize::from(*reg)] == 0 {
+                        next_pc = loc.0;
+                    }
+                }
+                Instruction::Jnz(reg, loc) => {
+                    if registers[usize::from(*reg)] != 0 {
+                        next_pc = loc.0;
+                    }
+                }
+                Instruction::Lb(dst, addr) => {
+                    let value = *memory
+                        .get(&addr.0)
+                        .unwrap_or(&0);
+                    registers[usize::from(*dst)] = value;
+                    memory_address = Some(addr.0);
+                    memory_value = Some(value);
+                }
+                Instruction::Sb(src, addr) => {
+                    let value = registers[usize::from(*src)];
+                    memory.insert(addr.0, value);
+                    memory_address = Some(addr.0);
+                    memory_value = Some(value);
+                }
+                Instruction::Halt => {
+                    next_pc = pc;
+                }
+            }
+
+            let is_halt = matches!(instruction, Instruction::Halt);
+            steps.push(ExecutionStep {
+                pc,
+                instruction: instruction.clone(),
+                registers_before,
+                registers_after: registers,
+                memory_address,
+                memory_value,
+                next_pc,
+            });
+
+            if is_halt {
+                break;
+            }
+            pc = next_pc;
+        }
+
+        steps
+    }
+}